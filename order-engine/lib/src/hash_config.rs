@@ -0,0 +1,128 @@
+//! Domain-separation tags for every SHA256 the protocol computes
+
+/// Tag prepended to each hash input, one field per hash kind.
+///
+/// `HashConfig::DEFAULT` is the single authoritative list; every hash in this crate reads its tag
+/// from it. A fork that changes any tag produces different hashes throughout and cannot accept
+/// commitments, nullifiers or proofs from this deployment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashConfig {
+    /// `OrderDomain::separator`
+    pub order_domain: &'static [u8],
+    /// `compute_nullifier_hash`
+    pub nullifier_hash: &'static [u8],
+    /// `compute_commitment_hash`
+    pub commitment_hash: &'static [u8],
+    /// Merkle internal nodes (commitment tree and legacy balance tree)
+    pub merkle_node: &'static [u8],
+    /// Legacy balance tree leaves (`verify_merkle_proof`)
+    pub balance_leaf: &'static [u8],
+    /// `generate_order_nullifier`
+    pub order_nullifier: &'static [u8],
+    /// `blind_commitment` (hiding Merkle leaves)
+    pub hiding_commitment: &'static [u8],
+    /// `bind_metadata` (audit metadata folded into a commitment)
+    pub order_metadata: &'static [u8],
+    /// `compute_ownership_hash` (balance-free half of a `SplitCommitment`)
+    pub order_ownership: &'static [u8],
+    /// `compute_balance_commitment`
+    pub balance_commitment: &'static [u8],
+    /// `compute_balance_hiding_commitment` (commitment over a blinded balance)
+    pub balance_hiding_commitment: &'static [u8],
+    /// `batch_order_context` (per-order nullifier context in `create_order_commitments`)
+    pub order_context: &'static [u8],
+    /// `compute_price_leaf` (oracle price tree leaves)
+    pub price_leaf: &'static [u8],
+}
+
+impl HashConfig {
+    pub const ORDER_DOMAIN: &'static [u8] = b"ORDER_DOMAIN";
+    pub const NULLIFIER_HASH: &'static [u8] = b"NULLIFIER_HASH";
+    pub const COMMITMENT_HASH: &'static [u8] = b"COMMITMENT_HASH";
+    pub const MERKLE_NODE: &'static [u8] = b"MERKLE_NODE";
+    pub const BALANCE_LEAF: &'static [u8] = b"BALANCE_LEAF";
+    pub const ORDER_NULLIFIER: &'static [u8] = b"ORDER_NULLIFIER";
+    pub const HIDING_COMMITMENT: &'static [u8] = b"HIDING_COMMITMENT";
+    pub const ORDER_METADATA: &'static [u8] = b"ORDER_METADATA";
+    pub const ORDER_OWNERSHIP: &'static [u8] = b"ORDER_OWNERSHIP";
+    pub const BALANCE_COMMITMENT: &'static [u8] = b"BALANCE_COMMITMENT";
+    pub const BALANCE_HIDING_COMMITMENT: &'static [u8] = b"BALANCE_HIDING_COMMITMENT";
+    pub const ORDER_CONTEXT: &'static [u8] = b"ORDER_CONTEXT";
+    pub const PRICE_LEAF: &'static [u8] = b"PRICE_LEAF";
+
+    /// Tags of the deployed protocol
+    pub const DEFAULT: HashConfig = HashConfig {
+        order_domain: Self::ORDER_DOMAIN,
+        nullifier_hash: Self::NULLIFIER_HASH,
+        commitment_hash: Self::COMMITMENT_HASH,
+        merkle_node: Self::MERKLE_NODE,
+        balance_leaf: Self::BALANCE_LEAF,
+        order_nullifier: Self::ORDER_NULLIFIER,
+        hiding_commitment: Self::HIDING_COMMITMENT,
+        order_metadata: Self::ORDER_METADATA,
+        order_ownership: Self::ORDER_OWNERSHIP,
+        balance_commitment: Self::BALANCE_COMMITMENT,
+        balance_hiding_commitment: Self::BALANCE_HIDING_COMMITMENT,
+        order_context: Self::ORDER_CONTEXT,
+        price_leaf: Self::PRICE_LEAF,
+    };
+
+    /// Every tag with its field name, for review
+    pub const fn tags(&self) -> [(&'static str, &'static [u8]); 13] {
+        [
+            ("order_domain", self.order_domain),
+            ("nullifier_hash", self.nullifier_hash),
+            ("commitment_hash", self.commitment_hash),
+            ("merkle_node", self.merkle_node),
+            ("balance_leaf", self.balance_leaf),
+            ("order_nullifier", self.order_nullifier),
+            ("hiding_commitment", self.hiding_commitment),
+            ("order_metadata", self.order_metadata),
+            ("order_ownership", self.order_ownership),
+            ("balance_commitment", self.balance_commitment),
+            ("balance_hiding_commitment", self.balance_hiding_commitment),
+            ("order_context", self.order_context),
+            ("price_leaf", self.price_leaf),
+        ]
+    }
+
+    /// Whether no tag is reused for two different hash kinds
+    pub const fn tags_distinct(&self) -> bool {
+        let tags = self.tags();
+        let mut i = 0;
+        while i < tags.len() {
+            let mut j = i + 1;
+            while j < tags.len() {
+                if bytes_eq(tags[i].1, tags[j].1) {
+                    return false;
+                }
+                j += 1;
+            }
+            i += 1;
+        }
+        true
+    }
+}
+
+impl Default for HashConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+// Reusing a tag would let one kind of hash be passed off as another
+const _: () = assert!(HashConfig::DEFAULT.tags_distinct());
+
+const fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
@@ -0,0 +1,59 @@
+//! Commitment leaf encodings for trees shared with partners' Merkle libraries
+
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+use crate::{compute_commitment_hash, hash_order, OrderData};
+
+/// How a commitment leaf is computed from an order, its nullifier and the balance.
+///
+/// Every encoding covers the full order through `hash_order`, and the nullifier keeps leaves
+/// unlinkable to the order as with the native leaf. Only `Sp1Native` leaves can be proven: the
+/// guest recomputes the native leaf. The others are for mirror trees kept for partner libraries
+/// and contracts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LeafEncoding {
+    /// `compute_commitment_hash`: `sha256("COMMITMENT_HASH" || order_hash || nullifier ||
+    /// balance_le_u64)`, the leaf the guest proves inclusion of
+    #[default]
+    Sp1Native,
+    /// `keccak256(abi.encode(bytes32 orderHash, bytes32 nullifier, uint256 balance))`: three
+    /// 32-byte words (96 bytes), the balance big-endian and left-padded with zeros, as
+    /// `merkletreejs` with `keccak256` or Solidity computes it
+    AbiEncodedKeccak,
+    /// `sha256(order_hash || nullifier || balance_be_u64)`: 72 bytes with no domain tag, as
+    /// Cairo and plain-SHA256 trees hash raw concatenations
+    RawConcatSha256,
+}
+
+impl LeafEncoding {
+    /// Merkle leaf of `order` under this encoding
+    pub fn commitment_leaf(
+        self,
+        order: &OrderData,
+        nullifier: &[u8; 32],
+        balance: u64,
+    ) -> [u8; 32] {
+        match self {
+            LeafEncoding::Sp1Native => compute_commitment_hash(order, nullifier, balance),
+            LeafEncoding::AbiEncodedKeccak => {
+                let mut balance_word = [0u8; 32];
+                balance_word[24..].copy_from_slice(&balance.to_be_bytes());
+
+                let mut hasher = Keccak256::new();
+                hasher.update(hash_order(order));
+                hasher.update(nullifier);
+                hasher.update(balance_word);
+                hasher.finalize().into()
+            }
+            LeafEncoding::RawConcatSha256 => {
+                let mut hasher = Sha256::new();
+                hasher.update(hash_order(order));
+                hasher.update(nullifier);
+                hasher.update(balance.to_be_bytes());
+                hasher.finalize().into()
+            }
+        }
+    }
+}
@@ -2,10 +2,86 @@ use alloy_sol_types::sol;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+mod hash_config;
+mod leaf;
+mod market;
+mod oz_tree;
+mod spent;
+#[cfg(feature = "sdk")]
+mod stdin;
+mod tokens;
+mod tree;
+mod types;
+#[cfg(feature = "wasm")]
+mod wasm;
 
+pub use hash_config::HashConfig;
+pub use leaf::LeafEncoding;
+pub use market::{MarketConditionsBuilder, MarketError};
+pub use oz_tree::{process_oz_proof, OzCompatTree};
+pub use spent::{
+    prove_not_spent, spent_nullifier_tree, verify_not_spent, NeighborProof, NonMembershipProof,
+    SPENT_HIGH_SENTINEL, SPENT_LOW_SENTINEL,
+};
+#[cfg(feature = "sdk")]
+pub use stdin::build_stdin;
+pub use tokens::{TokenConfig, TokenRegistry};
+pub use tree::{
+    verify_tree_root, CommitmentMerkleTree, MerkleProof, TreeError, TreeLevels, MAX_LEAVES,
+};
+pub use types::{Address, Hash32, TokenAddress};
+
+/// Evaluates `$body` as a named region of the executor's cycle report
+/// (`ExecutionReport::cycle_tracker`); the markers are only printed inside the zkVM
+macro_rules! cycle_region {
+    ($name:literal, $body:expr) => {{
+        #[cfg(target_os = "zkvm")]
+        println!(concat!("cycle-tracker-report-start: ", $name));
+        let value = $body;
+        #[cfg(target_os = "zkvm")]
+        println!(concat!("cycle-tracker-report-end: ", $name));
+        value
+    }};
+}
+
+/// Version of the guest's input/output layout; bump whenever inputs or committed outputs change
+pub const PROTOCOL_VERSION: u32 = 19;
+
+/// Deepest commitment Merkle path accepted anywhere: the tree holds at most
+/// `2^MAX_TREE_DEPTH` leaves, the server rejects longer sibling lists and the guest asserts it
+pub const MAX_TREE_DEPTH: usize = 20;
+
+/// Most orders one batch proof may hold; `BatchProofInputs::order_inputs` rejects a larger batch
+/// before evaluating any of it, so a batch cannot outgrow the zkVM's memory
+pub const MAX_BATCH_SIZE: usize = 32;
+
+/// Deployment an order is scoped to (chain + pool), mixed into every order hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderDomain {
+    pub chain_id: u64,
+    pub pool_address: [u8; 20],
+    /// Width order amounts are hashed at; see `AmountRepr`
+    #[serde(default)]
+    pub amount_repr: AmountRepr,
+}
+
+impl OrderDomain {
+    /// Computes the domain separator prepended to order hashes
+    pub fn separator(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(HashConfig::DEFAULT.order_domain); // Domain separation
+        hasher.update(self.chain_id.to_le_bytes());
+        hasher.update(self.pool_address);
+        if self.amount_repr == AmountRepr::U32 {
+            hasher.update([1u8]); // `U64` separators are unchanged
+        }
+        hasher.finalize().into()
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderData {
+    pub domain: OrderDomain,
     pub wallet_address: [u8; 20],
     pub token_in: [u8; 20],
     pub token_out: [u8; 20],
@@ -13,12 +89,289 @@ pub struct OrderData {
     pub min_amount_out: u64,
     pub target_price: u64,
     pub deadline: u64,
+    pub kind: OrderKind,
+    /// Largest deviation of `current_price` from the market's `reference_price`, in basis
+    /// points, the order may execute at; `None` for no cap
+    pub max_price_impact_bps: Option<u32>,
+    /// Protocol fee taken from the gross output, in basis points (at most `MAX_FEE_BPS`)
+    pub fee_bps: u16,
+    /// Orders sharing a group settle all-or-nothing (`evaluate_order_group`); `None` for a
+    /// standalone order
+    pub group_id: Option<[u8; 32]>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Largest `fee_bps` an order can carry: the whole output
+pub const MAX_FEE_BPS: u16 = 10_000;
+
+/// Scale of `target_price` and `current_price`: output units per `PRICE_SCALE` input units
+/// (USDC with 6 decimals per 10^18 wei)
+pub const PRICE_SCALE: u128 = 1_000_000_000_000_000_000;
+
+/// Which side of an order is fixed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum OrderKind {
+    /// Sell exactly `amount_in`, receiving at least `min_amount_out`
+    #[default]
+    ExactIn,
+    /// Receive exactly `exact_amount_out`, spending at most `max_amount_in` at the market price;
+    /// the order's `amount_in` and `min_amount_out` are ignored
+    ExactOut {
+        exact_amount_out: u64,
+        max_amount_in: u64,
+    },
+}
+
+impl OrderData {
+    /// Amounts the order settles with at `current_price`: `(amount_in, min_amount_out)`.
+    ///
+    /// Exact-out orders spend the input needed to buy `exact_amount_out` at that price, rounded
+    /// up; `None` if it cannot be computed (zero price or overflow).
+    pub fn settlement_amounts(&self, current_price: u64) -> Option<(u64, u64)> {
+        match self.kind {
+            OrderKind::ExactIn => Some((self.amount_in, self.min_amount_out)),
+            OrderKind::ExactOut {
+                exact_amount_out, ..
+            } => Some((
+                required_amount_in(exact_amount_out, current_price)?,
+                exact_amount_out,
+            )),
+        }
+    }
+
+    /// Seconds from `block_timestamp` to the deadline, negative once the order has expired
+    /// (saturating at the `i64` bounds)
+    pub fn seconds_to_deadline(&self, block_timestamp: u64) -> i64 {
+        let remaining = i128::from(self.deadline) - i128::from(block_timestamp);
+        remaining.clamp(i64::MIN.into(), i64::MAX.into()) as i64
+    }
+}
+
+/// Output `order` receives at `current_price` before fees: `amount_in` converted at the price
+/// (rounded down) for exact-in orders, `exact_amount_out` for exact-out orders. `None` if it
+/// does not fit in a `u64`.
+pub fn gross_amount_out(order: &OrderData, current_price: u64) -> Option<u64> {
+    match order.kind {
+        // A raw price is already per base unit, so it converts at equal decimals
+        OrderKind::ExactIn => expected_output(order.amount_in, current_price, 0, 0).ok(),
+        OrderKind::ExactOut {
+            exact_amount_out, ..
+        } => Some(exact_amount_out),
+    }
+}
+
+/// Protocol fee on `gross_out`: `gross_out * fee_bps / 10_000`, rounded down. `fee_bps` above
+/// `MAX_FEE_BPS` is treated as `MAX_FEE_BPS`; `validate_order` rejects such orders anyway.
+pub fn fee_amount(gross_out: u64, fee_bps: u16) -> u64 {
+    (gross_out as u128 * fee_bps.min(MAX_FEE_BPS) as u128 / 10_000) as u64
+}
+
+/// Whether `balance` covers the input `order` actually spends at `current_price`: `amount_in`
+/// for exact-in orders, the computed input for exact-out orders (never `max_amount_in` or the
+/// ignored `amount_in`).
+///
+/// Balances and inputs are both in token_in base units, so token decimals never enter the
+/// comparison. False when the input cannot be computed.
+pub fn balance_covers_order(order: &OrderData, balance: u64, current_price: u64) -> bool {
+    order
+        .settlement_amounts(current_price)
+        .is_some_and(|(amount_in, _)| balance >= amount_in)
+}
+
+/// Whether `price` is within `max_bps` basis points of `reference_price`; never true without a
+/// reference price
+pub fn within_price_impact(price: u64, reference_price: u64, max_bps: u32) -> bool {
+    if reference_price == 0 {
+        return false;
+    }
+    let deviation = price.abs_diff(reference_price) as u128 * 10_000;
+    deviation <= max_bps as u128 * reference_price as u128
+}
+
+/// Output, in token_out base units, that `amount_in` token_in base units convert to at
+/// `price`: whole token_out per `PRICE_SCALE` whole token_in, with `in_decimals` and
+/// `out_decimals` the tokens' decimals.
+///
+/// Computed as `amount_in * price * 10^out_decimals / (PRICE_SCALE * 10^in_decimals)` in `u128`
+/// and rounded down (floor) once at the end, so the output is never overstated. Raw prices
+/// (`current_price`, `target_price`) are already per base unit and convert at equal decimals.
+/// Fails with `OrderError::AmountOverflow` when the output does not fit in a `u64`.
+pub fn expected_output(
+    amount_in: u64,
+    price: u64,
+    in_decimals: u8,
+    out_decimals: u8,
+) -> Result<u64, OrderError> {
+    // Cannot overflow: a product of two u64s fits in a u128
+    let value = amount_in as u128 * price as u128;
+    if value == 0 {
+        return Ok(0);
+    }
+
+    let output = if out_decimals >= in_decimals {
+        10u128
+            .checked_pow(u32::from(out_decimals - in_decimals))
+            .and_then(|scale| value.checked_mul(scale))
+            .ok_or(OrderError::AmountOverflow)?
+            / PRICE_SCALE
+    } else {
+        // A divisor past u128 leaves nothing of any u64 output
+        match 10u128
+            .checked_pow(u32::from(in_decimals - out_decimals))
+            .and_then(|scale| scale.checked_mul(PRICE_SCALE))
+        {
+            Some(divisor) => value / divisor,
+            None => 0,
+        }
+    };
+    u64::try_from(output).map_err(|_| OrderError::AmountOverflow)
+}
+
+/// Input needed to receive `amount_out` at `price`, rounded up so the output is always covered
+pub fn required_amount_in(amount_out: u64, price: u64) -> Option<u64> {
+    if price == 0 {
+        return None;
+    }
+    let amount_in = (amount_out as u128 * PRICE_SCALE).div_ceil(price as u128);
+    u64::try_from(amount_in).ok()
+}
+
+/// `amount` of a token with `decimals` decimals as a decimal string, keeping at least one
+/// fractional digit (`5000000000000000000` at 18 decimals is `"5.0"`)
+pub fn format_units(amount: u64, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    let digits = format!("{amount:0>width$}", width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    format!(
+        "{whole}.{}",
+        if fraction.is_empty() { "0" } else { fraction }
+    )
+}
+
+/// Which order fields the guest commits as public outputs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum DisclosureLevel {
+    /// Commit every settlement field (wallet, amounts, fee)
+    #[default]
+    Full,
+    /// Commit no settlement fields, keeping the wallet and amounts hidden; the nullifier hash,
+    /// roots, domain and market timestamp are still committed
+    MinimalNullifierOnly,
+}
+
+impl std::str::FromStr for DisclosureLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(DisclosureLevel::Full),
+            "minimal" | "minimal-nullifier-only" => Ok(DisclosureLevel::MinimalNullifierOnly),
+            other => Err(format!(
+                "unknown disclosure level '{other}', expected \"full\" or \"minimal\""
+            )),
+        }
+    }
+}
+
+/// Width of the nullifier hash the guest commits and settlement stores.
+///
+/// `Bits128` keeps the low 16 bytes and zeroes the high 16, so the hash fits a `uint128` slot
+/// at half the storage cost. This weakens collision resistance from 128 to 64 bits (birthday
+/// bound): after about 2^64 orders, two nullifiers are likely to share a hash, and spending one
+/// would block the other. Targeting one given order still takes about 2^128 work. Settlement must
+/// reject proofs committing any width but its own, or a nullifier could be spent once in each
+/// form.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NullifierBits {
+    #[default]
+    Bits256,
+    Bits128,
+}
+
+impl NullifierBits {
+    pub const fn bits(self) -> u16 {
+        match self {
+            NullifierBits::Bits256 => 256,
+            NullifierBits::Bits128 => 128,
+        }
+    }
+
+    /// `hash` as published at this width
+    pub fn truncate(self, mut hash: [u8; 32]) -> [u8; 32] {
+        if self == NullifierBits::Bits128 {
+            hash[..16].fill(0);
+        }
+        hash
+    }
+}
+
+/// Width order amounts (`amount_in`, `min_amount_out` and the exact-out amounts) are hashed at
+/// in `hash_order`, declared per deployment in `OrderDomain`.
+///
+/// `U32` hashes 4 bytes per amount instead of 8, for tokens whose amounts never exceed
+/// `u32::MAX` base units. The modes are incompatible: an order hashes differently under each
+/// (the mode is also mixed into the domain separator), so a commitment made under one never
+/// verifies under the other and a deployment cannot switch without recommitting its orders.
+/// `validate_order` rejects `U32` orders with a larger amount. Committed outputs stay `u64`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum AmountRepr {
+    #[default]
+    U64,
+    U32,
+}
+
+impl AmountRepr {
+    /// Whether `amount` is representable at this width
+    pub fn fits(self, amount: u64) -> bool {
+        self == AmountRepr::U64 || amount <= u64::from(u32::MAX)
+    }
+
+    /// Hashes `amount` little-endian at this width; `U32` truncates, so callers check `fits`
+    fn hash_amount(self, hasher: &mut Sha256, amount: u64) {
+        match self {
+            AmountRepr::U64 => hasher.update(amount.to_le_bytes()),
+            AmountRepr::U32 => hasher.update((amount as u32).to_le_bytes()),
+        }
+    }
+}
+
+impl std::str::FromStr for AmountRepr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "u64" => Ok(AmountRepr::U64),
+            "u32" => Ok(AmountRepr::U32),
+            other => Err(format!(
+                "unsupported amount width '{other}', expected u64 or u32"
+            )),
+        }
+    }
+}
+
+impl std::str::FromStr for NullifierBits {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "256" => Ok(NullifierBits::Bits256),
+            "128" => Ok(NullifierBits::Bits128),
+            other => Err(format!(
+                "unsupported nullifier width '{other}', expected 256 or 128"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MarketConditions {
     pub current_price: u64,
     pub block_timestamp: u64,
+    /// Independent reference (e.g. oracle mid) for price-impact caps; 0 when unavailable
+    pub reference_price: u64,
 }
 
 /// Nullifier-based order commitment
@@ -29,6 +382,177 @@ pub struct OrderCommitment {
     pub balance: u64,        // User's private balance
 }
 
+/// Every guest input, public and private, in the order the guest reads them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderProofInputs {
+    // Public
+    pub market: MarketConditions,
+    pub merkle_root: [u8; 32],
+    pub nullifier_hash: [u8; 32],
+    pub domain: OrderDomain,
+    pub disclosure: DisclosureLevel,
+    /// Width `nullifier_hash` is given and committed at
+    pub nullifier_bits: NullifierBits,
+    /// Address settlement pays the protocol fee to, committed with the fee amount
+    pub fee_recipient: [u8; 20],
+    /// Also re-derive the statement through `cross_check_statement` and commit whether both
+    /// paths agree; the guest refuses to prove when they do not. Doubles the order checks, so
+    /// meant for execution, not production proofs.
+    pub cross_check: bool,
+    // Private
+    pub order: OrderData,
+    pub nullifier: [u8; 32],
+    pub balance: u64,
+    pub siblings: Vec<[u8; 32]>,
+    pub indices: Vec<u8>,
+    /// Set when the Merkle leaf is a hiding commitment (`blind_commitment`)
+    pub blinding: Option<[u8; 32]>,
+    /// Hash of off-chain audit metadata bound into the commitment (`bind_metadata`); all zero
+    /// when the order carries none
+    pub metadata_hash: [u8; 32],
+    /// Set when `balance` is proven against a balance tree rather than bound in the commitment;
+    /// the order's Merkle leaf is then `compute_ownership_hash`, which survives balance changes
+    pub balance_proof: Option<BalanceProof>,
+    /// Set to check in the guest that the nullifier is not in a spent-nullifier tree, rather
+    /// than leaving replay protection to settlement alone
+    pub spent_proof: Option<NonMembershipProof>,
+    /// Set when the commitment binds `compute_balance_commitment(balance, balance_blinding)`
+    /// rather than the raw balance (`compute_balance_hiding_commitment`); the guest still checks
+    /// the opened `balance` covers the order. Excludes `balance_proof`.
+    pub balance_blinding: Option<[u8; 32]>,
+    /// Set to check in the guest that `market.current_price` is the price an oracle committed
+    /// for the order's token pair, rather than trusting the prover's market data
+    pub price_proof: Option<PriceProof>,
+}
+
+/// Inclusion of the order wallet's balance in a separately maintained balance tree, whose leaves
+/// are `compute_balance_leaf` and whose nodes are hashed like the commitment tree's
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceProof {
+    /// Public balance tree root, committed as `GuestOutputs::balance_root`
+    pub root: [u8; 32],
+    pub siblings: Vec<[u8; 32]>,
+    pub indices: Vec<u8>,
+}
+
+/// Inclusion of the market price in an oracle's price tree, whose leaves are
+/// `compute_price_leaf` and whose nodes are hashed like the commitment tree's
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceProof {
+    /// Public price tree root, committed as `GuestOutputs::price_root`
+    pub root: [u8; 32],
+    pub siblings: Vec<[u8; 32]>,
+    pub indices: Vec<u8>,
+}
+
+/// Each check of the proof statement and the resulting validity the guest commits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatementResult {
+    pub computed_nullifier_hash: [u8; 32],
+    pub nullifier_hash_valid: bool,
+    pub domain_valid: bool,
+    pub merkle_valid: bool,
+    pub order_executable: bool,
+    /// The balance is in the balance tree (always true when it is bound in the commitment)
+    pub balance_valid: bool,
+    /// The nullifier is absent from the spent set (always true when no set is checked)
+    pub not_spent: bool,
+    /// The market price is in the oracle price tree (always true when no tree is checked)
+    pub price_valid: bool,
+    pub metadata_present: bool,
+    /// Input the order spends and the output it must receive (computed for exact-out orders);
+    /// zero when they cannot be computed
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+    /// Protocol fee on the gross output at the market price; zero when it cannot be computed
+    pub fee_amount: u64,
+    pub valid: bool,
+    /// `cross_check_statement` result, when `OrderProofInputs::cross_check` is set
+    pub cross_check_ok: Option<bool>,
+}
+
+/// Everything the guest commits, as a single value: hosts read it with
+/// `public_values.read::<GuestOutputs>()` instead of field by field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestOutputs {
+    pub valid: bool,
+    /// Truncated to `nullifier_bits`
+    pub nullifier_hash: [u8; 32],
+    /// Settlement must check this is the width it stores nullifier hashes at
+    pub nullifier_bits: NullifierBits,
+    /// Commitment tree root the order was proven against; settlement must check it is a root
+    /// of the pool's commitment tree, or any prover could prove against a tree of their own
+    pub merkle_root: [u8; 32],
+    /// Chain and pool the order is scoped to, at every disclosure level: the nullifier hash does
+    /// not bind them, so settlement must check this is its own deployment
+    pub domain: OrderDomain,
+    /// Market timestamp the order was validated at, at every disclosure level, so settlement
+    /// and the server can reject stale proofs (`is_fresh`)
+    pub block_timestamp: u64,
+    pub metadata_present: bool,
+    /// Balance tree root the balance was proven against; settlement must check it is a current
+    /// root. `None` when the balance is bound in the commitment.
+    pub balance_root: Option<[u8; 32]>,
+    /// Spent-nullifier tree root the nullifier was proven absent from; settlement must check it
+    /// is current. `None` when the guest did not check the spent set.
+    pub spent_root: Option<[u8; 32]>,
+    /// Oracle price tree root the market price was proven against; settlement must check it is
+    /// a root the oracle published. `None` when the price was not checked.
+    pub price_root: Option<[u8; 32]>,
+    /// Whether the reference cross-check agreed with the statement; `None` unless requested by
+    /// `OrderProofInputs::cross_check`
+    pub cross_check_ok: Option<bool>,
+    /// Only disclosed at `DisclosureLevel::Full`
+    pub settlement: Option<SettlementFields>,
+}
+
+/// Order and market fields settlement needs to execute the swap
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettlementFields {
+    pub wallet_address: [u8; 20],
+    /// Computed input for exact-out orders
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+    /// `fee_amount` of the gross output at the market price, owed to `fee_recipient`
+    pub fee_amount: u64,
+    pub fee_recipient: [u8; 20],
+}
+
+impl GuestOutputs {
+    /// Outputs for `statement` evaluated over `inputs`, disclosing settlement fields only at
+    /// `DisclosureLevel::Full`
+    pub fn new(inputs: &OrderProofInputs, statement: &StatementResult) -> Self {
+        GuestOutputs {
+            valid: statement.valid,
+            nullifier_hash: statement.computed_nullifier_hash,
+            nullifier_bits: inputs.nullifier_bits,
+            merkle_root: inputs.merkle_root,
+            domain: inputs.domain,
+            block_timestamp: inputs.market.block_timestamp,
+            metadata_present: statement.metadata_present,
+            balance_root: inputs.balance_proof.as_ref().map(|proof| proof.root),
+            spent_root: inputs.spent_proof.as_ref().map(|proof| proof.root),
+            price_root: inputs.price_proof.as_ref().map(|proof| proof.root),
+            cross_check_ok: statement.cross_check_ok,
+            settlement: (inputs.disclosure == DisclosureLevel::Full).then_some(SettlementFields {
+                wallet_address: inputs.order.wallet_address,
+                amount_in: statement.amount_in,
+                min_amount_out: statement.min_amount_out,
+                fee_amount: statement.fee_amount,
+                fee_recipient: inputs.fee_recipient,
+            }),
+        }
+    }
+}
+
+/// Order commitment split so ownership can be shown without the balance: `ownership_hash`
+/// binds the wallet, order and nullifier, while the balance is only in `balance_commitment`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitCommitment {
+    pub ownership_hash: [u8; 32],
+    pub balance_commitment: [u8; 32],
+}
+
 /// Public nullifier data for preventing double-spending
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NullifierData {
@@ -36,53 +560,346 @@ pub struct NullifierData {
     pub commitment_hash: [u8; 32], // Public commitment hash (goes in Merkle tree)
 }
 
-/// Validates order conditions including market and time constraints
+/// Reasons an order fails `validate_order`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderError {
+    /// `token_in == token_out`, or either token is the zero address
+    InvalidTokenPair,
+    /// The market timestamp is past the order deadline
+    Expired,
+    /// The market price is below the order's target price
+    PriceBelowTarget,
+    /// The order does not hash to the expected order hash
+    HashMismatch,
+    /// An exact-out order needs more input than its `max_amount_in` at the market price
+    InputCapExceeded,
+    /// `current_price` deviates from `reference_price` by more than the order's
+    /// `max_price_impact_bps`, or there is no reference price to check against
+    PriceImpactExceeded,
+    /// The order moves no tokens: a zero `amount_in` or `min_amount_out` (for exact-out
+    /// orders, a zero `max_amount_in` or `exact_amount_out`)
+    ZeroAmount,
+    /// `fee_bps` is above `MAX_FEE_BPS`
+    FeeTooHigh,
+    /// An amount converted at the market price does not fit in a `u64`
+    AmountOverflow,
+    /// An order amount does not fit the domain's `AmountRepr`
+    AmountOutOfRange,
+}
+
+impl std::fmt::Display for OrderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            OrderError::InvalidTokenPair => "token_in and token_out must differ and be non-zero",
+            OrderError::Expired => "order deadline has passed",
+            OrderError::PriceBelowTarget => "market price is below the target price",
+            OrderError::HashMismatch => "order does not match the expected hash",
+            OrderError::PriceImpactExceeded => {
+                "market price deviates from the reference price by more than max_price_impact_bps"
+            }
+            OrderError::InputCapExceeded => {
+                "exact-out order needs more input than max_amount_in at the market price"
+            }
+            OrderError::ZeroAmount => "amount_in and min_amount_out must be non-zero",
+            OrderError::FeeTooHigh => "fee_bps must be at most 10000",
+            OrderError::AmountOverflow => "converted amount does not fit in a u64",
+            OrderError::AmountOutOfRange => "order amount does not fit the domain's amount width",
+        })
+    }
+}
+
+impl std::error::Error for OrderError {}
+
+/// Reasons `OrderProofInputs::validate` rejects a guest input before any zkVM work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputError {
+    /// `siblings` and `indices` describe paths of different lengths
+    PathLengthMismatch { siblings: usize, indices: usize },
+    /// The Merkle path is deeper than `MAX_TREE_DEPTH`
+    PathTooDeep(usize),
+    /// A path index other than 0 (left) or 1 (right)
+    InvalidIndex { level: usize, index: u8 },
+    /// The all-zero nullifier, which anyone could claim
+    ZeroNullifier,
+    /// Both `balance_proof` and `balance_blinding` are set; a leaf proves its balance one way
+    BalanceBindingConflict,
+}
+
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::PathLengthMismatch { siblings, indices } => write!(
+                f,
+                "{siblings} siblings but {indices} indices; the Merkle path needs one index per sibling"
+            ),
+            InputError::PathTooDeep(depth) => write!(
+                f,
+                "Merkle path has {depth} levels, more than MAX_TREE_DEPTH ({MAX_TREE_DEPTH})"
+            ),
+            InputError::InvalidIndex { level, index } => {
+                write!(f, "index {index} at level {level} must be 0 or 1")
+            }
+            InputError::ZeroNullifier => f.write_str("nullifier must not be all zero"),
+            InputError::BalanceBindingConflict => {
+                f.write_str("balance_proof and balance_blinding cannot both be set")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+impl OrderProofInputs {
+    /// Checks the structural invariants the guest relies on, so malformed inputs fail here
+    /// rather than as a guest panic or a silently invalid proof
+    pub fn validate(&self) -> Result<(), InputError> {
+        if self.nullifier == [0u8; 32] {
+            return Err(InputError::ZeroNullifier);
+        }
+        check_path(&self.siblings, &self.indices)?;
+        if let Some(proof) = &self.balance_proof {
+            if self.balance_blinding.is_some() {
+                return Err(InputError::BalanceBindingConflict);
+            }
+            check_path(&proof.siblings, &proof.indices)?;
+        }
+        if let Some(proof) = &self.spent_proof {
+            check_path(&proof.low.siblings, &proof.low.indices)?;
+            check_path(&proof.high.siblings, &proof.high.indices)?;
+        }
+        if let Some(proof) = &self.price_proof {
+            check_path(&proof.siblings, &proof.indices)?;
+        }
+        Ok(())
+    }
+}
+
+/// Path shape rules shared by the commitment, balance and price trees
+fn check_path(siblings: &[[u8; 32]], indices: &[u8]) -> Result<(), InputError> {
+    if siblings.len() != indices.len() {
+        return Err(InputError::PathLengthMismatch {
+            siblings: siblings.len(),
+            indices: indices.len(),
+        });
+    }
+
+    if siblings.len() > MAX_TREE_DEPTH {
+        return Err(InputError::PathTooDeep(siblings.len()));
+    }
+
+    if let Some((level, &index)) = indices.iter().enumerate().find(|(_, &i)| i > 1) {
+        return Err(InputError::InvalidIndex { level, index });
+    }
+
+    Ok(())
+}
+
+/// Validates order conditions including token pair, non-zero amounts, market and time constraints
 pub fn validate_order(
     order: &OrderData,
     market: &MarketConditions,
     expected_hash: &[u8; 32],
-) -> bool {
+) -> Result<(), OrderError> {
+    if order.token_in == order.token_out
+        || order.token_in == [0u8; 20]
+        || order.token_out == [0u8; 20]
+    {
+        return Err(OrderError::InvalidTokenPair);
+    }
+
+    // Exact-out orders ignore the exact-in fields, so check the amounts they settle by instead
+    let (amount_in, min_amount_out) = match order.kind {
+        OrderKind::ExactIn => (order.amount_in, order.min_amount_out),
+        OrderKind::ExactOut {
+            exact_amount_out,
+            max_amount_in,
+        } => (max_amount_in, exact_amount_out),
+    };
+    if amount_in == 0 || min_amount_out == 0 {
+        return Err(OrderError::ZeroAmount);
+    }
+
+    // `hash_order` truncates amounts to the domain's width, so a wider one must not pass
+    let fits = |amount| order.domain.amount_repr.fits(amount);
+    let exact_out_fits = match order.kind {
+        OrderKind::ExactIn => true,
+        OrderKind::ExactOut {
+            exact_amount_out,
+            max_amount_in,
+        } => fits(exact_amount_out) && fits(max_amount_in),
+    };
+    if !(fits(order.amount_in) && fits(order.min_amount_out) && exact_out_fits) {
+        return Err(OrderError::AmountOutOfRange);
+    }
+
+    if order.fee_bps > MAX_FEE_BPS {
+        return Err(OrderError::FeeTooHigh);
+    }
+
     if market.block_timestamp > order.deadline {
-        return false;
+        return Err(OrderError::Expired);
     }
 
     if market.current_price < order.target_price {
-        return false;
+        return Err(OrderError::PriceBelowTarget);
+    }
+
+    if let Some(max_bps) = order.max_price_impact_bps {
+        if !within_price_impact(market.current_price, market.reference_price, max_bps) {
+            return Err(OrderError::PriceImpactExceeded);
+        }
+    }
+
+    if let OrderKind::ExactOut { max_amount_in, .. } = order.kind {
+        match order.settlement_amounts(market.current_price) {
+            Some((amount_in, _)) if amount_in <= max_amount_in => {}
+            _ => return Err(OrderError::InputCapExceeded),
+        }
     }
 
     let computed_hash = hash_order(order);
-    computed_hash == *expected_hash
+    if computed_hash != *expected_hash {
+        return Err(OrderError::HashMismatch);
+    }
+
+    Ok(())
+}
+
+/// Which of `markets` `order` would execute against: `validate_order` on each, in order.
+///
+/// Host-side routing aid for relayers choosing a venue before proving; nothing is proven.
+pub fn executable_markets(order: &OrderData, markets: &[MarketConditions]) -> Vec<bool> {
+    let order_hash = hash_order(order);
+    markets
+        .iter()
+        .map(|market| validate_order(order, market, &order_hash).is_ok())
+        .collect()
+}
+
+/// What an executable order clears at: its output at the market price before and after the
+/// protocol fee
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Execution {
+    /// `gross_amount_out` at `clearing_price`
+    pub gross_out: u64,
+    /// `fee_amount` of `gross_out`, owed to the fee recipient
+    pub fee_amount: u64,
+    /// `gross_out - fee_amount`, what the order's wallet receives
+    pub net_out: u64,
+    /// Market price the order executes at (`current_price`, raw `PRICE_SCALE` units)
+    pub clearing_price: u64,
+}
+
+/// Checks `order` is executable in `market` (`validate_order` against its own hash) and returns
+/// the clearing details. Fails with the first condition the order violates, or
+/// `AmountOverflow` when the gross output does not fit in a `u64`.
+pub fn compute_execution(
+    order: &OrderData,
+    market: &MarketConditions,
+) -> Result<Execution, OrderError> {
+    validate_order(order, market, &hash_order(order))?;
+
+    let gross_out =
+        gross_amount_out(order, market.current_price).ok_or(OrderError::AmountOverflow)?;
+    let fee_amount = fee_amount(gross_out, order.fee_bps);
+    Ok(Execution {
+        gross_out,
+        fee_amount,
+        net_out: gross_out - fee_amount,
+        clearing_price: market.current_price,
+    })
+}
+
+/// Returns whether a proof whose committed market timestamp is `block_timestamp` may still
+/// settle at `now`, given a freshness window in seconds. Timestamps ahead of `now` (clock skew)
+/// count as fresh.
+pub fn is_fresh(block_timestamp: u64, now: u64, window_secs: u64) -> bool {
+    now.saturating_sub(block_timestamp) <= window_secs
 }
 
-/// Computes deterministic hash of order data
+/// Computes deterministic hash of order data, scoped to the order's domain
 pub fn hash_order(order: &OrderData) -> [u8; 32] {
+    let amount_repr = order.domain.amount_repr;
     let mut hasher = Sha256::new();
-    hasher.update(&order.wallet_address);
-    hasher.update(&order.token_in);
-    hasher.update(&order.token_out);
-    hasher.update(&order.amount_in.to_le_bytes());
-    hasher.update(&order.min_amount_out.to_le_bytes());
-    hasher.update(&order.target_price.to_le_bytes());
-    hasher.update(&order.deadline.to_le_bytes());
+    hasher.update(order.domain.separator());
+    hasher.update(order.wallet_address);
+    hasher.update(order.token_in);
+    hasher.update(order.token_out);
+    amount_repr.hash_amount(&mut hasher, order.amount_in);
+    amount_repr.hash_amount(&mut hasher, order.min_amount_out);
+    hasher.update(order.target_price.to_le_bytes());
+    hasher.update(order.deadline.to_le_bytes());
+    match order.kind {
+        OrderKind::ExactIn => hasher.update([0u8]),
+        OrderKind::ExactOut {
+            exact_amount_out,
+            max_amount_in,
+        } => {
+            hasher.update([1u8]);
+            amount_repr.hash_amount(&mut hasher, exact_amount_out);
+            amount_repr.hash_amount(&mut hasher, max_amount_in);
+        }
+    }
+    match order.max_price_impact_bps {
+        None => hasher.update([0u8]),
+        Some(max_bps) => {
+            hasher.update([1u8]);
+            hasher.update(max_bps.to_le_bytes());
+        }
+    }
+    hasher.update(order.fee_bps.to_le_bytes());
+    match order.group_id {
+        None => hasher.update([0u8]),
+        Some(group_id) => {
+            hasher.update([1u8]);
+            hasher.update(group_id);
+        }
+    }
     hasher.finalize().into()
 }
 
 /// Computes nullifier hash from private nullifier (prevents double-spending)
 pub fn compute_nullifier_hash(nullifier: &[u8; 32]) -> [u8; 32] {
     let mut hasher = Sha256::new();
-    hasher.update(b"NULLIFIER_HASH"); // Domain separation
+    hasher.update(HashConfig::DEFAULT.nullifier_hash); // Domain separation
     hasher.update(nullifier);
     hasher.finalize().into()
 }
 
+/// `compute_nullifier_hash` at the width a deployment publishes; see `NullifierBits` for what
+/// 128-bit mode gives up
+pub fn compute_nullifier_hash_truncated(nullifier: &[u8; 32], bits: NullifierBits) -> [u8; 32] {
+    bits.truncate(compute_nullifier_hash(nullifier))
+}
+
+/// Nullifier hashes of `commitments`, in the same order, for the settlement contract to mark
+/// spent after a batch.
+///
+/// Any batch output must list its nullifier hashes in exactly this order so the host side can
+/// be checked against it element by element.
+pub fn batch_nullifier_hashes(commitments: &[OrderCommitment]) -> Vec<[u8; 32]> {
+    commitments
+        .iter()
+        .map(|commitment| compute_nullifier_hash(&commitment.nullifier))
+        .collect()
+}
+
 /// Computes commitment hash from order, nullifier, and balance (goes in Merkle tree)
 pub fn compute_commitment_hash(order: &OrderData, nullifier: &[u8; 32], balance: u64) -> [u8; 32] {
+    compute_commitment_hash_from_order_hash(&hash_order(order), nullifier, balance)
+}
+
+/// Computes commitment hash from an already computed order hash
+pub fn compute_commitment_hash_from_order_hash(
+    order_hash: &[u8; 32],
+    nullifier: &[u8; 32],
+    balance: u64,
+) -> [u8; 32] {
     let mut hasher = Sha256::new();
-    hasher.update(b"COMMITMENT_HASH"); // Domain separation
+    hasher.update(HashConfig::DEFAULT.commitment_hash); // Domain separation
 
     // Hash order data
-    let order_hash = hash_order(order);
-    hasher.update(&order_hash);
+    hasher.update(order_hash);
 
     // Hash nullifier
     hasher.update(nullifier);
@@ -93,12 +910,116 @@ pub fn compute_commitment_hash(order: &OrderData, nullifier: &[u8; 32], balance:
     hasher.finalize().into()
 }
 
-/// Verifies that the user knows the nullifier for their commitment
+/// Folds an audit metadata hash into a commitment hash, so the metadata can later be revealed
+/// and checked against the Merkle leaf. An all-zero `metadata_hash` means no metadata and leaves
+/// the commitment unchanged.
+pub fn bind_metadata(commitment_hash: &[u8; 32], metadata_hash: &[u8; 32]) -> [u8; 32] {
+    if *metadata_hash == [0u8; 32] {
+        return *commitment_hash;
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(HashConfig::DEFAULT.order_metadata); // Domain separation
+    hasher.update(commitment_hash);
+    hasher.update(metadata_hash);
+    hasher.finalize().into()
+}
+
+/// Mixes a random blinder into a commitment hash, so the Merkle leaf reveals nothing about the
+/// order even to someone who can guess all of its fields
+pub fn blind_commitment(commitment_hash: &[u8; 32], blinding: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(HashConfig::DEFAULT.hiding_commitment); // Domain separation
+    hasher.update(commitment_hash);
+    hasher.update(blinding);
+    hasher.finalize().into()
+}
+
+/// Computes the hiding Merkle leaf of an order: its commitment hash blinded with `blinding`
+pub fn compute_hiding_commitment(
+    order: &OrderData,
+    nullifier: &[u8; 32],
+    balance: u64,
+    blinding: &[u8; 32],
+) -> [u8; 32] {
+    blind_commitment(
+        &compute_commitment_hash(order, nullifier, balance),
+        blinding,
+    )
+}
+
+/// Hashes the wallet, order and nullifier without the balance
+pub fn compute_ownership_hash(order: &OrderData, nullifier: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(HashConfig::DEFAULT.order_ownership); // Domain separation
+    hasher.update(order.wallet_address);
+    hasher.update(hash_order(order));
+    hasher.update(nullifier);
+    hasher.finalize().into()
+}
+
+/// Hides a balance behind a random blinder; without it the few plausible balances could be
+/// brute-forced
+pub fn compute_balance_commitment(balance: u64, blinding: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(HashConfig::DEFAULT.balance_commitment); // Domain separation
+    hasher.update(balance.to_le_bytes());
+    hasher.update(blinding);
+    hasher.finalize().into()
+}
+
+/// Commitment hash binding `compute_balance_commitment(balance, balance_blinding)` in place of
+/// the raw balance, so revealing the order and nullifier still does not reveal the balance.
+/// The guest opens it with the balance and blinder and checks the balance covers the order.
+pub fn compute_balance_hiding_commitment(
+    order: &OrderData,
+    nullifier: &[u8; 32],
+    balance: u64,
+    balance_blinding: &[u8; 32],
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(HashConfig::DEFAULT.balance_hiding_commitment); // Domain separation
+    hasher.update(hash_order(order));
+    hasher.update(nullifier);
+    hasher.update(compute_balance_commitment(balance, balance_blinding));
+    hasher.finalize().into()
+}
+
+/// Commits an order with its balance kept in a separate, blinded commitment
+pub fn create_split_commitment(
+    order: &OrderData,
+    nullifier: &[u8; 32],
+    balance: u64,
+    balance_blinding: &[u8; 32],
+) -> SplitCommitment {
+    SplitCommitment {
+        ownership_hash: compute_ownership_hash(order, nullifier),
+        balance_commitment: compute_balance_commitment(balance, balance_blinding),
+    }
+}
+
+/// Checks that `commitment` is owned by `wallet`: the order names that wallet and, with the
+/// nullifier, opens `commitment.ownership_hash`. The balance is never needed.
+pub fn verify_wallet_ownership(
+    commitment: &SplitCommitment,
+    order: &OrderData,
+    wallet: &Address,
+    nullifier: &[u8; 32],
+) -> bool {
+    order.wallet_address == wallet.0
+        && compute_ownership_hash(order, nullifier) == commitment.ownership_hash
+}
+
+/// Verifies that the user knows the nullifier for their commitment. The all-zero nullifier
+/// never passes: everyone knows it.
 pub fn verify_nullifier_knowledge(
     commitment: &OrderCommitment,
     expected_commitment_hash: &[u8; 32],
     expected_nullifier_hash: &[u8; 32],
 ) -> bool {
+    if commitment.nullifier == [0u8; 32] {
+        return false;
+    }
+
     // Verify commitment hash matches
     let computed_commitment = compute_commitment_hash(
         &commitment.order_data,
@@ -120,63 +1041,142 @@ pub fn verify_nullifier_knowledge(
 }
 
 /// Verifies Merkle proof for commitment hash (not individual balance)
+///
+/// Empty proofs are rejected: they would accept any commitment presented as its own root.
+/// `CommitmentMerkleTree` never produces one; see `verify_single_leaf_root` for the explicit
+/// opt-in.
 pub fn verify_commitment_merkle_proof(
     commitment_hash: &[u8; 32],
-    siblings: &Vec<[u8; 32]>,
-    indices: &Vec<u8>,
-    expected_root: &[u8; 32],
+    proof: &MerkleProof,
+    expected_root: &Hash32,
 ) -> bool {
-    if siblings.len() != indices.len() {
+    if proof.path.is_empty() || proof.path.len() > MAX_TREE_DEPTH {
         return false;
     }
 
-    let mut current_hash = *commitment_hash;
+    let root = proof
+        .path
+        .iter()
+        .fold(*commitment_hash, |node, (index, sibling)| {
+            merkle_parent(&node, sibling, *index)
+        });
+    root == expected_root.0
+}
+
+/// Every node `verify_commitment_merkle_proof` recomputes, for inspecting a failing proof level
+/// by level
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleVerifyTrace {
+    /// The leaf, then the node computed at each level; the last entry is the computed root
+    pub nodes: Vec<[u8; 32]>,
+    pub computed_root: [u8; 32],
+    /// Same result `verify_commitment_merkle_proof` returns for these inputs
+    pub valid: bool,
+}
+
+impl MerkleVerifyTrace {
+    /// First position in `nodes` where the two traces differ (a sibling corrupted at level `i`
+    /// first shows up at `i + 1`), or `None` if one trace is a prefix of the other
+    pub fn first_divergence(&self, other: &MerkleVerifyTrace) -> Option<usize> {
+        self.nodes
+            .iter()
+            .zip(&other.nodes)
+            .position(|(a, b)| a != b)
+    }
+}
+
+/// `verify_commitment_merkle_proof` that also returns the recomputed path.
+///
+/// Debugging aid only: compare the trace against a known-good one (e.g. from the prover's own
+/// tree) with `MerkleVerifyTrace::first_divergence` to find the level a bad sibling or index sits
+/// at.
+pub fn verify_commitment_merkle_proof_verbose(
+    commitment_hash: &[u8; 32],
+    proof: &MerkleProof,
+    expected_root: &Hash32,
+) -> MerkleVerifyTrace {
+    let mut nodes = vec![*commitment_hash];
+    for (index, sibling) in &proof.path {
+        let parent = merkle_parent(nodes.last().unwrap(), sibling, *index);
+        nodes.push(parent);
+    }
+    let computed_root = *nodes.last().unwrap();
+
+    MerkleVerifyTrace {
+        nodes,
+        computed_root,
+        valid: verify_commitment_merkle_proof(commitment_hash, proof, expected_root),
+    }
+}
+
+/// Single-leaf rule: accepts a "tree" of exactly one commitment whose root is the commitment
+/// itself, with no Merkle path.
+///
+/// Only for callers that deliberately build such roots by hand; it proves nothing beyond
+/// knowing the root, so the guest never uses it.
+pub fn verify_single_leaf_root(commitment_hash: &[u8; 32], expected_root: &Hash32) -> bool {
+    *commitment_hash == expected_root.0
+}
+
+/// Folds a leaf up a Merkle path and returns the resulting root.
+///
+/// `indices[i] == 0` means the running node is the left child at level `i`. Callers must check
+/// that `siblings` and `indices` have the same length; extra entries of either are ignored.
+pub fn compute_merkle_root(leaf: &[u8; 32], siblings: &[[u8; 32]], indices: &[u8]) -> [u8; 32] {
+    let mut current_hash = *leaf;
 
     // Traverse up the tree
-    for (i, sibling) in siblings.iter().enumerate() {
-        let mut hasher = Sha256::new();
-        hasher.update(b"MERKLE_NODE"); // Domain separation
+    for (sibling, index) in siblings.iter().zip(indices) {
+        current_hash = merkle_parent(&current_hash, sibling, *index);
+    }
 
-        if indices[i] == 0 {
-            // Current node is left child
-            hasher.update(&current_hash);
-            hasher.update(sibling);
-        } else {
-            // Current node is right child
-            hasher.update(sibling);
-            hasher.update(&current_hash);
-        }
-        current_hash = hasher.finalize().into();
+    current_hash
+}
+
+/// Parent of `node` and its `sibling`; `index == 0` means `node` is the left child
+fn merkle_parent(node: &[u8; 32], sibling: &[u8; 32], index: u8) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(HashConfig::DEFAULT.merkle_node); // Domain separation
+
+    if index == 0 {
+        // Current node is left child
+        hasher.update(node);
+        hasher.update(sibling);
+    } else {
+        // Current node is right child
+        hasher.update(sibling);
+        hasher.update(node);
     }
+    hasher.finalize().into()
+}
 
-    &current_hash == expected_root
+/// Balance tree leaf: H(address || balance)
+pub fn compute_balance_leaf(address: &Address, balance: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(HashConfig::DEFAULT.balance_leaf); // Domain separation
+    hasher.update(address.0);
+    hasher.update(balance.to_le_bytes());
+    hasher.finalize().into()
 }
 
-/// Legacy balance verification for backward compatibility
+/// Balance tree verification (`OrderProofInputs::balance_proof`), also the legacy balance check
 pub fn verify_merkle_proof(
-    address: &[u8; 20],
+    address: &Address,
     balance: u64,
     siblings: &Vec<[u8; 32]>,
     indices: &Vec<u8>,
-    expected_root: &[u8; 32],
+    expected_root: &Hash32,
 ) -> bool {
     if siblings.len() != indices.len() {
         return false;
     }
 
-    // Compute leaf: H(address || balance)
-    let mut hasher = Sha256::new();
-    hasher.update(b"BALANCE_LEAF"); // Domain separation
-    hasher.update(address);
-    hasher.update(&balance.to_le_bytes());
-    let result = hasher.finalize();
-    let mut current_hash = [0u8; 32];
-    current_hash.copy_from_slice(&result);
+    let mut current_hash = compute_balance_leaf(address, balance);
 
     // Traverse up the tree
     for (i, sibling) in siblings.iter().enumerate() {
         let mut hasher = Sha256::new();
-        hasher.update(b"MERKLE_NODE"); // Domain separation
+        hasher.update(HashConfig::DEFAULT.merkle_node); // Domain separation
 
         if indices[i] == 0 {
             hasher.update(&current_hash);
@@ -188,7 +1188,59 @@ pub fn verify_merkle_proof(
         current_hash = hasher.finalize().into();
     }
 
-    &current_hash == expected_root
+    current_hash == expected_root.0
+}
+
+/// Oracle price tree leaf: H(token_in || token_out || price). The pair is ordered, so a price
+/// for one direction is never accepted for the other.
+pub fn compute_price_leaf(
+    token_in: &TokenAddress,
+    token_out: &TokenAddress,
+    price: u64,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(HashConfig::DEFAULT.price_leaf); // Domain separation
+    hasher.update(token_in.0);
+    hasher.update(token_out.0);
+    hasher.update(price.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// Whether `price` is the oracle's price for `token_pair` (`(token_in, token_out)`) in the
+/// price tree with root `price_root` (`OrderProofInputs::price_proof`)
+pub fn verify_price_inclusion(
+    token_pair: (&TokenAddress, &TokenAddress),
+    price: u64,
+    siblings: &[[u8; 32]],
+    indices: &[u8],
+    price_root: &Hash32,
+) -> bool {
+    let leaf = compute_price_leaf(token_pair.0, token_pair.1, price);
+    MerkleProof::from_parts(siblings, indices)
+        .is_ok_and(|proof| verify_commitment_merkle_proof(&leaf, &proof, price_root))
+}
+
+/// Commitment tree leaf replacing the legacy balance tree leaf `compute_balance_leaf(address,
+/// balance)`: the commitment hash of the `order` placed from `address` with that `balance`.
+///
+/// To migrate a balance tree deployment, the relayer collects each user's pending order and
+/// nullifier (only the user can derive the nullifier), checks the user's old leaf against the
+/// balance tree root with `verify_merkle_proof`, and builds the commitment tree from the migrated
+/// leaves with `CommitmentMerkleTree::from_sorted`. Users then prove inclusion against the new
+/// root with `verify_commitment_merkle_proof`.
+///
+/// Panics if `address` is not the order's `wallet_address`.
+pub fn migrate_balance_leaf_to_commitment(
+    address: &Address,
+    balance: u64,
+    order: &OrderData,
+    nullifier: &[u8; 32],
+) -> [u8; 32] {
+    assert_eq!(
+        address.0, order.wallet_address,
+        "balance leaf address is not the order's wallet"
+    );
+    compute_commitment_hash(order, nullifier, balance)
 }
 
 /// Verifies that an order can be executed with nullifier protection
@@ -203,14 +1255,18 @@ pub fn verify_nullifier_order(
         return false;
     }
 
-    // 2. Verify sufficient balance
-    if commitment.balance < commitment.order_data.amount_in {
+    // 2. Verify sufficient balance for the input actually spent
+    if !balance_covers_order(
+        &commitment.order_data,
+        commitment.balance,
+        market.current_price,
+    ) {
         return false;
     }
 
     // 3. Verify order conditions
     let order_hash = hash_order(&commitment.order_data);
-    if !validate_order(&commitment.order_data, market, &order_hash) {
+    if validate_order(&commitment.order_data, market, &order_hash).is_err() {
         return false;
     }
 
@@ -223,12 +1279,20 @@ pub fn generate_order_nullifier(
     order_context: &[u8; 32], // Could be order hash or trading session ID
 ) -> [u8; 32] {
     let mut hasher = Sha256::new();
-    hasher.update(b"ORDER_NULLIFIER"); // Domain separation
+    hasher.update(HashConfig::DEFAULT.order_nullifier); // Domain separation
     hasher.update(user_secret);
     hasher.update(order_context);
     hasher.finalize().into()
 }
 
+/// Nullifier hash to publish when cancelling the order committed with this secret and context.
+///
+/// It is the hash executing the order would publish, so once settlement marks it spent the order
+/// can never be executed; only the holder of `user_secret` can compute the preimage behind it.
+pub fn cancel_nullifier_hash(user_secret: &[u8; 32], order_context: &[u8; 32]) -> [u8; 32] {
+    compute_nullifier_hash(&generate_order_nullifier(user_secret, order_context))
+}
+
 /// Creates order commitment for Merkle tree inclusion
 pub fn create_order_commitment(
     order: &OrderData,
@@ -239,6 +1303,50 @@ pub fn create_order_commitment(
     // Generate nullifier
     let nullifier = generate_order_nullifier(user_secret, order_context);
 
+    create_order_commitment_with_nullifier(order, nullifier, balance)
+        .expect("derived nullifier is a SHA256 output, never all zero")
+}
+
+/// Nullifier context of the `index`-th order in a `create_order_commitments` batch: the order
+/// hash and position, so identical orders in one batch still get distinct nullifiers.
+///
+/// Keep it (or the order and index) to cancel the order later with `cancel_nullifier_hash`.
+pub fn batch_order_context(order: &OrderData, index: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(HashConfig::DEFAULT.order_context); // Domain separation
+    hasher.update(hash_order(order));
+    hasher.update(index.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// `create_order_commitment` for many orders from one user, each with its own nullifier derived
+/// from `batch_order_context`; results are in the order of `orders`
+pub fn create_order_commitments(
+    orders: &[OrderData],
+    user_secret: &[u8; 32],
+    balance: u64,
+) -> Vec<(OrderCommitment, NullifierData)> {
+    orders
+        .iter()
+        .enumerate()
+        .map(|(index, order)| {
+            let context = batch_order_context(order, index as u64);
+            create_order_commitment(order, user_secret, balance, &context)
+        })
+        .collect()
+}
+
+/// Creates an order commitment for a nullifier the caller manages itself (e.g. one held by a
+/// hardware wallet) instead of deriving it from a user secret
+pub fn create_order_commitment_with_nullifier(
+    order: &OrderData,
+    nullifier: [u8; 32],
+    balance: u64,
+) -> Result<(OrderCommitment, NullifierData), InputError> {
+    if nullifier == [0u8; 32] {
+        return Err(InputError::ZeroNullifier);
+    }
+
     // Create commitment
     let commitment = OrderCommitment {
         order_data: order.clone(),
@@ -252,5 +1360,690 @@ pub fn create_order_commitment(
         commitment_hash: compute_commitment_hash(order, &nullifier, balance),
     };
 
-    (commitment, nullifier_data)
+    Ok((commitment, nullifier_data))
+}
+
+/// Evaluates the full proof statement over `inputs` exactly as the guest does.
+///
+/// The guest calls this function itself, so running it natively reproduces the committed
+/// `valid` without SP1.
+pub fn evaluate_order_statement(inputs: &OrderProofInputs) -> StatementResult {
+    // 1. Verify nullifier hash matches expected (prevents replay attacks)
+    // 2. Compute commitment hash from private inputs
+    let (full_nullifier_hash, commitment_hash) = cycle_region!("hashing", {
+        (
+            compute_nullifier_hash(&inputs.nullifier),
+            compute_commitment_hash(&inputs.order, &inputs.nullifier, inputs.balance),
+        )
+    });
+    let computed_nullifier_hash = inputs.nullifier_bits.truncate(full_nullifier_hash);
+    let nullifier_hash_valid = computed_nullifier_hash == inputs.nullifier_hash;
+
+    // 3. Create commitment struct for verification
+    let commitment = OrderCommitment {
+        order_data: inputs.order.clone(),
+        nullifier: inputs.nullifier,
+        balance: inputs.balance,
+    };
+
+    // 4. Verify the order was committed for this deployment (prevents cross-pool replay)
+    let domain_valid = inputs.order.domain == inputs.domain;
+
+    // 5. Verify commitment is in the Merkle tree (metadata bound first, then blinded when the
+    //    leaf is hiding)
+    //    The leaf binds the balance unless it is proven against the balance tree, and binds it
+    //    blinded when a balance blinder is given. Every variant hashes `hash_order` of the order
+    //    executed below, so inclusion is what ties the settled amounts to the committed order.
+    let merkle_valid = cycle_region!("merkle", {
+        let order_leaf = match (&inputs.balance_proof, &inputs.balance_blinding) {
+            (Some(_), _) => compute_ownership_hash(&inputs.order, &inputs.nullifier),
+            (None, Some(balance_blinding)) => compute_balance_hiding_commitment(
+                &inputs.order,
+                &inputs.nullifier,
+                inputs.balance,
+                balance_blinding,
+            ),
+            (None, None) => commitment_hash,
+        };
+        let bound_commitment = bind_metadata(&order_leaf, &inputs.metadata_hash);
+        let leaf = match &inputs.blinding {
+            Some(blinding) => blind_commitment(&bound_commitment, blinding),
+            None => bound_commitment,
+        };
+        MerkleProof::from_parts(&inputs.siblings, &inputs.indices).is_ok_and(|proof| {
+            verify_commitment_merkle_proof(&leaf, &proof, &Hash32(inputs.merkle_root))
+        })
+    });
+
+    // 5b. Verify the balance against the balance tree, when it is not bound in the leaf
+    let balance_valid = match &inputs.balance_proof {
+        Some(proof) => cycle_region!(
+            "balance_merkle",
+            !proof.siblings.is_empty()
+                && verify_merkle_proof(
+                    &Address(inputs.order.wallet_address),
+                    inputs.balance,
+                    &proof.siblings,
+                    &proof.indices,
+                    &Hash32(proof.root),
+                )
+        ),
+        None => true,
+    };
+
+    // 5c. Verify the nullifier has not been spent, when a spent set is supplied
+    let not_spent = match &inputs.spent_proof {
+        Some(proof) => cycle_region!(
+            "spent_set",
+            verify_not_spent(&computed_nullifier_hash, proof)
+        ),
+        None => true,
+    };
+
+    // 5d. Verify the market price against the oracle price tree, when one is supplied
+    let price_valid = match &inputs.price_proof {
+        Some(proof) => cycle_region!(
+            "price_merkle",
+            verify_price_inclusion(
+                (
+                    &TokenAddress(inputs.order.token_in),
+                    &TokenAddress(inputs.order.token_out),
+                ),
+                inputs.market.current_price,
+                &proof.siblings,
+                &proof.indices,
+                &Hash32(proof.root),
+            )
+        ),
+        None => true,
+    };
+
+    // 6. Verify order execution conditions
+    let order_executable = cycle_region!(
+        "order_checks",
+        verify_nullifier_order(
+            &commitment,
+            &inputs.market,
+            &commitment_hash,
+            &full_nullifier_hash,
+        )
+    );
+
+    // 7. Amounts to settle at the market price (exact-out orders derive their input) and the
+    //    protocol fee on the gross output
+    let (amount_in, min_amount_out) = inputs
+        .order
+        .settlement_amounts(inputs.market.current_price)
+        .unwrap_or((0, 0));
+    let fee_amount = gross_amount_out(&inputs.order, inputs.market.current_price)
+        .map_or(0, |gross_out| fee_amount(gross_out, inputs.order.fee_bps));
+
+    let mut statement = StatementResult {
+        computed_nullifier_hash,
+        nullifier_hash_valid,
+        domain_valid,
+        merkle_valid,
+        order_executable,
+        balance_valid,
+        not_spent,
+        price_valid,
+        metadata_present: inputs.metadata_hash != [0u8; 32],
+        amount_in,
+        min_amount_out,
+        fee_amount,
+        valid: nullifier_hash_valid
+            && domain_valid
+            && merkle_valid
+            && order_executable
+            && balance_valid
+            && not_spent
+            && price_valid,
+        cross_check_ok: None,
+    };
+
+    // 8. Optionally re-derive the checks through the standalone helpers
+    if inputs.cross_check {
+        statement.cross_check_ok = Some(cycle_region!(
+            "cross_check",
+            cross_check_statement(inputs, &statement)
+        ));
+    }
+    statement
+}
+
+/// Recomputes every check and settled amount of `statement` straight from the standalone
+/// helpers rather than the wiring in `evaluate_order_statement`, and returns whether they agree.
+/// A refactor that changes one path but not the other (e.g. passing the truncated nullifier hash
+/// to `verify_nullifier_order`, or folding the Merkle path from the wrong leaf) makes this
+/// `false`.
+pub fn cross_check_statement(inputs: &OrderProofInputs, statement: &StatementResult) -> bool {
+    let order = &inputs.order;
+    let price = inputs.market.current_price;
+    let commitment = OrderCommitment {
+        order_data: order.clone(),
+        nullifier: inputs.nullifier,
+        balance: inputs.balance,
+    };
+    let order_hash = hash_order(order);
+    let commitment_hash =
+        compute_commitment_hash_from_order_hash(&order_hash, &inputs.nullifier, inputs.balance);
+    let computed_nullifier_hash =
+        compute_nullifier_hash_truncated(&inputs.nullifier, inputs.nullifier_bits);
+
+    // Commitment and price tree paths: non-empty and at most MAX_TREE_DEPTH deep
+    let tree_path_reaches = |leaf: &[u8; 32], siblings: &[[u8; 32]], indices: &[u8], root| {
+        !siblings.is_empty()
+            && siblings.len() <= MAX_TREE_DEPTH
+            && siblings.len() == indices.len()
+            && compute_merkle_root(leaf, siblings, indices) == root
+    };
+
+    let nullifier_hash_valid = computed_nullifier_hash == inputs.nullifier_hash;
+    let domain_valid = order.domain.separator() == inputs.domain.separator();
+    let order_leaf = match (&inputs.balance_proof, &inputs.balance_blinding) {
+        (Some(_), _) => compute_ownership_hash(order, &inputs.nullifier),
+        (None, Some(balance_blinding)) => compute_balance_hiding_commitment(
+            order,
+            &inputs.nullifier,
+            inputs.balance,
+            balance_blinding,
+        ),
+        (None, None) => commitment_hash,
+    };
+    let leaf = match &inputs.blinding {
+        Some(blinding) => {
+            blind_commitment(&bind_metadata(&order_leaf, &inputs.metadata_hash), blinding)
+        }
+        None => bind_metadata(&order_leaf, &inputs.metadata_hash),
+    };
+    let merkle_valid =
+        tree_path_reaches(&leaf, &inputs.siblings, &inputs.indices, inputs.merkle_root);
+    // Balance tree paths have no depth limit
+    let balance_valid = match &inputs.balance_proof {
+        Some(proof) => {
+            !proof.siblings.is_empty()
+                && proof.siblings.len() == proof.indices.len()
+                && compute_merkle_root(
+                    &compute_balance_leaf(&Address(order.wallet_address), inputs.balance),
+                    &proof.siblings,
+                    &proof.indices,
+                ) == proof.root
+        }
+        None => true,
+    };
+    let not_spent = match &inputs.spent_proof {
+        Some(proof) => verify_not_spent(&computed_nullifier_hash, proof),
+        None => true,
+    };
+    let price_valid = match &inputs.price_proof {
+        Some(proof) => tree_path_reaches(
+            &compute_price_leaf(
+                &TokenAddress(order.token_in),
+                &TokenAddress(order.token_out),
+                price,
+            ),
+            &proof.siblings,
+            &proof.indices,
+            proof.root,
+        ),
+        None => true,
+    };
+    let order_executable = verify_nullifier_order(
+        &commitment,
+        &inputs.market,
+        &commitment_hash,
+        &compute_nullifier_hash(&inputs.nullifier),
+    );
+
+    let (amount_in, min_amount_out, gross_out) = match order.kind {
+        OrderKind::ExactIn => (
+            order.amount_in,
+            order.min_amount_out,
+            expected_output(order.amount_in, price, 0, 0).ok(),
+        ),
+        OrderKind::ExactOut {
+            exact_amount_out, ..
+        } => match required_amount_in(exact_amount_out, price) {
+            Some(amount_in) => (amount_in, exact_amount_out, Some(exact_amount_out)),
+            None => (0, 0, Some(exact_amount_out)),
+        },
+    };
+    let fee = gross_out.map_or(0, |gross_out| fee_amount(gross_out, order.fee_bps));
+
+    computed_nullifier_hash == statement.computed_nullifier_hash
+        && nullifier_hash_valid == statement.nullifier_hash_valid
+        && domain_valid == statement.domain_valid
+        && merkle_valid == statement.merkle_valid
+        && balance_valid == statement.balance_valid
+        && not_spent == statement.not_spent
+        && price_valid == statement.price_valid
+        && order_executable == statement.order_executable
+        && amount_in == statement.amount_in
+        && min_amount_out == statement.min_amount_out
+        && fee == statement.fee_amount
+}
+
+/// Guest input of a batch proof. The orders, their commitment tree inclusion proofs and their
+/// expected nullifier hashes arrive as separate lists, whose `i`-th entries all belong to the
+/// `i`-th order (the order `batch_nullifier_hashes` lists the hashes in).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchProofInputs {
+    /// Each order's guest inputs; their own `siblings`, `indices` and `nullifier_hash` are
+    /// replaced by the matching entries of `proofs` and `nullifier_hashes`
+    pub orders: Vec<OrderProofInputs>,
+    pub proofs: Vec<MerkleProof>,
+    pub nullifier_hashes: Vec<[u8; 32]>,
+}
+
+/// Reasons a batch is rejected before any of its orders is evaluated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchError {
+    /// More orders than `MAX_BATCH_SIZE`
+    TooLarge { orders: usize },
+    /// The batch lists do not hold one proof and one nullifier hash per order
+    CountMismatch {
+        orders: usize,
+        proofs: usize,
+        nullifier_hashes: usize,
+    },
+}
+
+impl std::fmt::Display for BatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BatchError::TooLarge { orders } => write!(
+                f,
+                "batch of {orders} orders exceeds MAX_BATCH_SIZE ({MAX_BATCH_SIZE})"
+            ),
+            BatchError::CountMismatch {
+                orders,
+                proofs,
+                nullifier_hashes,
+            } => write!(
+                f,
+                "{orders} orders but {proofs} proofs and {nullifier_hashes} nullifier hashes; a batch needs one of each per order"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+impl BatchProofInputs {
+    /// Per-order guest inputs, in batch order, each with its own proof and nullifier hash.
+    /// Fails with `TooLarge` above `MAX_BATCH_SIZE` orders, and with `CountMismatch` unless the
+    /// three lists have the same length.
+    pub fn order_inputs(&self) -> Result<Vec<OrderProofInputs>, BatchError> {
+        let orders = self.orders.len();
+        if orders > MAX_BATCH_SIZE {
+            return Err(BatchError::TooLarge { orders });
+        }
+        if self.proofs.len() != orders || self.nullifier_hashes.len() != orders {
+            return Err(BatchError::CountMismatch {
+                orders,
+                proofs: self.proofs.len(),
+                nullifier_hashes: self.nullifier_hashes.len(),
+            });
+        }
+
+        Ok(self
+            .orders
+            .iter()
+            .zip(&self.proofs)
+            .zip(&self.nullifier_hashes)
+            .map(|((inputs, proof), nullifier_hash)| {
+                let (siblings, indices) = proof.clone().into_parts();
+                OrderProofInputs {
+                    nullifier_hash: *nullifier_hash,
+                    siblings,
+                    indices,
+                    ..inputs.clone()
+                }
+            })
+            .collect())
+    }
+}
+
+/// Outputs a batch proof commits for `batch`: one `GuestOutputs` per order, in input order, so
+/// settlement can attribute the `i`-th result and nullifier hash to the `i`-th order (the order
+/// `batch_nullifier_hashes` lists them in).
+///
+/// The counts are checked before any order is evaluated (`BatchProofInputs::order_inputs`), so
+/// a batch whose lists disagree fails outright rather than pairing an order with another order's
+/// proof or nullifier hash.
+pub fn evaluate_order_batch(batch: &BatchProofInputs) -> Result<Vec<GuestOutputs>, BatchError> {
+    evaluate_order_batch_with_progress(batch, |_| {})
+}
+
+/// How far a batch evaluation has got: `processed` of `total` orders are validated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchProgress {
+    pub processed: usize,
+    pub total: usize,
+}
+
+/// `evaluate_order_batch` calling `on_progress` after each order, so an operator can follow a
+/// large batch. Reports go from `1/n` to `n/n` in order; an empty batch reports nothing.
+pub fn evaluate_order_batch_with_progress(
+    batch: &BatchProofInputs,
+    mut on_progress: impl FnMut(BatchProgress),
+) -> Result<Vec<GuestOutputs>, BatchError> {
+    let orders = batch.order_inputs()?;
+    let total = orders.len();
+    Ok(orders
+        .iter()
+        .enumerate()
+        .map(|(i, inputs)| {
+            let outputs = GuestOutputs::new(inputs, &evaluate_order_statement(inputs));
+            on_progress(BatchProgress {
+                processed: i + 1,
+                total,
+            });
+            outputs
+        })
+        .collect())
+}
+
+/// Outputs a group proof commits for `batch`: `evaluate_order_batch`'s per-order outputs, plus
+/// the single `group_valid` bool settlement executes the group on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupOutputs {
+    /// `group_id` the orders share; zero when the first order has none
+    pub group_id: [u8; 32],
+    /// True only if every order carries `group_id` and is valid, so related orders settle
+    /// together or not at all
+    pub group_valid: bool,
+    pub orders: Vec<GuestOutputs>,
+}
+
+/// Evaluates `batch` as one all-or-nothing group. An empty batch, an order without a
+/// `group_id` or orders of different groups make `group_valid` false, as does any invalid order.
+/// Fails like `evaluate_order_batch` when the batch lists disagree in length.
+pub fn evaluate_order_group(batch: &BatchProofInputs) -> Result<GroupOutputs, BatchError> {
+    let group_id = batch
+        .orders
+        .first()
+        .and_then(|inputs| inputs.order.group_id);
+    let orders = evaluate_order_batch(batch)?;
+    let group_valid = group_id.is_some()
+        && batch
+            .orders
+            .iter()
+            .all(|inputs| inputs.order.group_id == group_id)
+        && orders.iter().all(|outputs| outputs.valid);
+
+    Ok(GroupOutputs {
+        group_id: group_id.unwrap_or_default(),
+        group_valid,
+        orders,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOMAIN: OrderDomain = OrderDomain {
+        chain_id: 17000,
+        pool_address: [0xCu8; 20],
+        amount_repr: AmountRepr::U64,
+    };
+
+    /// Sells 5 ETH for at least 10k USDC at $2000, open until timestamp 2000
+    fn order(wallet: u8) -> OrderData {
+        OrderData {
+            domain: DOMAIN,
+            wallet_address: [wallet; 20],
+            token_in: [0xAu8; 20],
+            token_out: [0xBu8; 20],
+            amount_in: 5_000_000_000_000_000_000,
+            min_amount_out: 10_000_000_000,
+            target_price: 2_000_000_000,
+            deadline: 2_000,
+            kind: OrderKind::ExactIn,
+            max_price_impact_bps: None,
+            fee_bps: 0,
+            group_id: None,
+        }
+    }
+
+    /// $2100 per ETH at timestamp 1000, so `order` executes
+    fn market() -> MarketConditions {
+        MarketConditions {
+            current_price: 2_100_000_000,
+            block_timestamp: 1_000,
+            reference_price: 0,
+        }
+    }
+
+    /// Commits `orders` in one tree (10 ETH balance each) and returns each order's guest inputs
+    /// against its root
+    fn committed(orders: &[OrderData]) -> Vec<OrderProofInputs> {
+        let commitments: Vec<_> = orders
+            .iter()
+            .enumerate()
+            .map(|(i, order)| {
+                create_order_commitment(
+                    order,
+                    &[i as u8 + 1; 32],
+                    10_000_000_000_000_000_000,
+                    &hash_order(order),
+                )
+            })
+            .collect();
+        let mut tree = CommitmentMerkleTree::<()>::new();
+        for (_, nullifier_data) in &commitments {
+            tree.add_commitment(nullifier_data.commitment_hash, ())
+                .unwrap();
+        }
+        let (merkle_root, _) = tree.build_tree().unwrap();
+
+        commitments
+            .into_iter()
+            .map(|(commitment, nullifier_data)| {
+                let (siblings, indices) = tree
+                    .generate_proof(nullifier_data.commitment_hash)
+                    .unwrap()
+                    .into_parts();
+                OrderProofInputs {
+                    market: market(),
+                    merkle_root,
+                    nullifier_hash: nullifier_data.nullifier_hash,
+                    domain: DOMAIN,
+                    disclosure: DisclosureLevel::Full,
+                    nullifier_bits: NullifierBits::Bits256,
+                    fee_recipient: [0xFu8; 20],
+                    cross_check: false,
+                    order: commitment.order_data,
+                    nullifier: commitment.nullifier,
+                    balance: commitment.balance,
+                    siblings,
+                    indices,
+                    blinding: None,
+                    metadata_hash: [0u8; 32],
+                    balance_proof: None,
+                    spent_proof: None,
+                    balance_blinding: None,
+                    price_proof: None,
+                }
+            })
+            .collect()
+    }
+
+    /// `orders` as a batch, with the proofs and nullifier hashes split out of each order
+    fn batch(orders: Vec<OrderProofInputs>) -> BatchProofInputs {
+        BatchProofInputs {
+            proofs: orders
+                .iter()
+                .map(|inputs| MerkleProof::from_parts(&inputs.siblings, &inputs.indices).unwrap())
+                .collect(),
+            nullifier_hashes: orders.iter().map(|inputs| inputs.nullifier_hash).collect(),
+            orders,
+        }
+    }
+
+    #[test]
+    fn expected_output_rescales_between_token_decimals() {
+        // 2000 USDC (6 decimals) at 0.0005 ETH (18 decimals) per USDC is 1 ETH
+        assert_eq!(
+            expected_output(2_000_000_000, 500_000_000_000_000, 6, 18),
+            Ok(1_000_000_000_000_000_000)
+        );
+        // 1 ETH at 0.05 WBTC (8 decimals) per ETH is 0.05 WBTC
+        assert_eq!(
+            expected_output(1_000_000_000_000_000_000, 50_000_000_000_000_000, 18, 8),
+            Ok(5_000_000)
+        );
+        // Equal decimals leave a raw price as it is
+        assert_eq!(
+            expected_output(5_000_000_000_000_000_000, 2_100_000_000, 18, 18),
+            expected_output(5_000_000_000_000_000_000, 2_100_000_000, 0, 0)
+        );
+    }
+
+    #[test]
+    fn expected_output_rounds_down_once_at_the_boundary() {
+        let price = PRICE_SCALE as u64;
+        assert_eq!(expected_output(1, price - 1, 0, 0), Ok(0));
+        assert_eq!(expected_output(1, price, 0, 0), Ok(1));
+        // One unit at 6 decimals is 10^12 units at 18: anything short of it floors to zero
+        assert_eq!(expected_output(999_999_999_999, price, 18, 6), Ok(0));
+        assert_eq!(expected_output(1_000_000_000_000, price, 18, 6), Ok(1));
+        assert_eq!(expected_output(1_999_999_999_999, price, 18, 6), Ok(1));
+    }
+
+    #[test]
+    fn expected_output_rejects_overflow() {
+        assert_eq!(
+            expected_output(u64::MAX, u64::MAX, 0, 0),
+            Err(OrderError::AmountOverflow)
+        );
+        assert_eq!(
+            expected_output(u64::MAX, PRICE_SCALE as u64, 0, 1),
+            Err(OrderError::AmountOverflow)
+        );
+        // Past u128, scaling up still overflows and scaling down leaves nothing
+        assert_eq!(
+            expected_output(1, 1, 0, 255),
+            Err(OrderError::AmountOverflow)
+        );
+        assert_eq!(expected_output(u64::MAX, u64::MAX, 255, 0), Ok(0));
+        assert_eq!(expected_output(0, u64::MAX, 0, 255), Ok(0));
+    }
+
+    #[test]
+    fn cross_check_catches_a_tampered_statement() {
+        let inputs = OrderProofInputs {
+            cross_check: true,
+            ..committed(&[order(1), order(2)]).remove(1)
+        };
+        let statement = evaluate_order_statement(&inputs);
+        assert!(statement.valid);
+        assert_eq!(statement.cross_check_ok, Some(true));
+
+        let tampered: [fn(&mut StatementResult); 8] = [
+            |s| s.domain_valid = false,
+            |s| s.merkle_valid = false,
+            |s| s.balance_valid = false,
+            |s| s.not_spent = false,
+            |s| s.price_valid = false,
+            |s| s.amount_in -= 1,
+            |s| s.min_amount_out += 1,
+            |s| s.fee_amount += 1,
+        ];
+        for tamper in tampered {
+            let mut statement = statement;
+            tamper(&mut statement);
+            assert!(!cross_check_statement(&inputs, &statement));
+        }
+    }
+
+    #[test]
+    fn same_order_on_two_chains_commits_differently() {
+        let holesky = order(1);
+        let mainnet = OrderData {
+            domain: OrderDomain {
+                chain_id: 1,
+                ..DOMAIN
+            },
+            ..order(1)
+        };
+        let nullifier = [7u8; 32];
+        let balance = 10_000_000_000_000_000_000;
+
+        assert_ne!(hash_order(&holesky), hash_order(&mainnet));
+        assert_ne!(
+            compute_commitment_hash(&holesky, &nullifier, balance),
+            compute_commitment_hash(&mainnet, &nullifier, balance)
+        );
+    }
+
+    #[test]
+    fn batch_outputs_follow_input_order_around_an_invalid_order() {
+        let expired = OrderData {
+            deadline: 500,
+            ..order(2)
+        };
+        let batch = batch(committed(&[order(1), expired, order(3)]));
+
+        let outputs = evaluate_order_batch(&batch).unwrap();
+
+        let valid: Vec<bool> = outputs.iter().map(|outputs| outputs.valid).collect();
+        assert_eq!(valid, [true, false, true]);
+        let committed_hashes: Vec<[u8; 32]> = outputs
+            .iter()
+            .map(|outputs| outputs.nullifier_hash)
+            .collect();
+        assert_eq!(committed_hashes, batch.nullifier_hashes);
+    }
+
+    #[test]
+    fn batch_with_a_missing_proof_or_nullifier_hash_is_rejected() {
+        let mut short_proofs = batch(committed(&[order(1), order(2), order(3)]));
+        short_proofs.proofs.pop();
+        assert_eq!(
+            evaluate_order_batch(&short_proofs),
+            Err(BatchError::CountMismatch {
+                orders: 3,
+                proofs: 2,
+                nullifier_hashes: 3
+            })
+        );
+
+        let mut short_hashes = batch(committed(&[order(1), order(2), order(3)]));
+        short_hashes.nullifier_hashes.pop();
+        assert!(evaluate_order_group(&short_hashes).is_err());
+    }
+
+    #[test]
+    fn minimal_disclosure_still_commits_a_stale_timestamp() {
+        let mut inputs = committed(&[order(1), order(2)]).remove(0);
+        inputs.disclosure = DisclosureLevel::MinimalNullifierOnly;
+        let outputs = GuestOutputs::new(&inputs, &evaluate_order_statement(&inputs));
+
+        assert!(outputs.valid);
+        assert_eq!(outputs.settlement, None);
+        assert_eq!(outputs.block_timestamp, market().block_timestamp);
+        assert!(is_fresh(outputs.block_timestamp, 1_300, 300));
+        assert!(!is_fresh(outputs.block_timestamp, 1_301, 300));
+    }
+
+    #[test]
+    fn batch_one_over_max_batch_size_is_rejected() {
+        let orders: Vec<OrderData> = (1..=MAX_BATCH_SIZE as u8 + 1).map(order).collect();
+        let mut over = batch(committed(&orders));
+        assert_eq!(
+            over.order_inputs().map(|orders| orders.len()),
+            Err(BatchError::TooLarge {
+                orders: MAX_BATCH_SIZE + 1
+            })
+        );
+
+        over.orders.pop();
+        over.proofs.pop();
+        over.nullifier_hashes.pop();
+        let outputs = evaluate_order_batch(&over).unwrap();
+        assert_eq!(outputs.len(), MAX_BATCH_SIZE);
+        assert!(outputs.iter().all(|outputs| outputs.valid));
+    }
 }
@@ -0,0 +1,115 @@
+//! Market conditions from human-readable prices instead of raw `PRICE_SCALE` units
+
+use crate::{MarketConditions, PRICE_SCALE};
+
+/// Reasons `MarketConditionsBuilder::build` rejects its inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketError {
+    /// No price was set, or it is not a positive number of at least one raw unit
+    ZeroPrice,
+    /// The price does not fit in a `u64` at `PRICE_SCALE`
+    PriceOverflow,
+    /// No block timestamp was set, or it is zero
+    MissingTimestamp,
+    /// A token of the pair is not in the `TokenRegistry`
+    UnknownToken,
+}
+
+impl std::fmt::Display for MarketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MarketError::ZeroPrice => "price must be positive and at least one raw price unit",
+            MarketError::PriceOverflow => "price does not fit in a u64 at PRICE_SCALE",
+            MarketError::MissingTimestamp => "block timestamp must be set and non-zero",
+            MarketError::UnknownToken => "token is not in the token registry",
+        })
+    }
+}
+
+impl std::error::Error for MarketError {}
+
+/// Builds `MarketConditions` from prices in whole tokens.
+///
+/// Decimals default to 18 for token_in and 6 for token_out (WETH/USDC), so
+/// `.price_usd(2050.0)` gives the raw `current_price` 2050000000.
+#[derive(Debug, Clone)]
+pub struct MarketConditionsBuilder {
+    price: Option<f64>,
+    reference_price: Option<f64>,
+    timestamp: u64,
+    token_in_decimals: u8,
+    token_out_decimals: u8,
+}
+
+impl MarketConditions {
+    pub fn builder() -> MarketConditionsBuilder {
+        MarketConditionsBuilder {
+            price: None,
+            reference_price: None,
+            timestamp: 0,
+            token_in_decimals: 18,
+            token_out_decimals: 6,
+        }
+    }
+}
+
+impl MarketConditionsBuilder {
+    /// Current price in whole token_out (e.g. USD for a USDC pair) per whole token_in
+    pub fn price_usd(mut self, price: f64) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    /// Reference price for price-impact caps, in the same units as `price_usd`; left at 0
+    /// (unavailable) when not set
+    pub fn reference_price_usd(mut self, price: f64) -> Self {
+        self.reference_price = Some(price);
+        self
+    }
+
+    /// Block timestamp the prices were observed at
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Decimals of token_in and token_out, used to convert whole-token prices
+    pub fn decimals(mut self, token_in: u8, token_out: u8) -> Self {
+        self.token_in_decimals = token_in;
+        self.token_out_decimals = token_out;
+        self
+    }
+
+    pub fn build(&self) -> Result<MarketConditions, MarketError> {
+        if self.timestamp == 0 {
+            return Err(MarketError::MissingTimestamp);
+        }
+
+        Ok(MarketConditions {
+            current_price: self.raw_price(self.price.ok_or(MarketError::ZeroPrice)?)?,
+            block_timestamp: self.timestamp,
+            reference_price: self
+                .reference_price
+                .map(|price| self.raw_price(price))
+                .transpose()?
+                .unwrap_or(0),
+        })
+    }
+
+    /// Token_out base units per `PRICE_SCALE` token_in base units, rounded to the nearest unit
+    fn raw_price(&self, price: f64) -> Result<u64, MarketError> {
+        // One power of ten keeps common prices exact (2050.0 * 10^6, not 2050.0 * 10^18 / 10^12)
+        let exponent = PRICE_SCALE.ilog10() as i32 + i32::from(self.token_out_decimals)
+            - i32::from(self.token_in_decimals);
+        let raw = (price * 10f64.powi(exponent)).round();
+
+        if raw.is_nan() || raw < 1.0 {
+            return Err(MarketError::ZeroPrice);
+        }
+        // u64::MAX as f64 rounds up to 2^64, which itself does not fit
+        if raw >= u64::MAX as f64 {
+            return Err(MarketError::PriceOverflow);
+        }
+        Ok(raw as u64)
+    }
+}
@@ -0,0 +1,111 @@
+//! Merkle tree compatible with OpenZeppelin's `MerkleProof` (Solidity settlement)
+
+use sha3::{Digest, Keccak256};
+
+use crate::tree::{TreeError, MAX_LEAVES};
+
+/// Merkle tree hashed the way OpenZeppelin's `MerkleProof.processProof` expects: each pair is
+/// sorted before hashing and nodes are bare `keccak256(a || b)` with no domain tag.
+///
+/// Not interchangeable with `CommitmentMerkleTree`, whose roots the guest verifies; this is for
+/// contracts that check leaf membership with the OZ library. Leaves are used as given, so
+/// callers following OZ's `StandardMerkleTree` must hash them the same way first. An unpaired
+/// node is carried up to the next level unchanged.
+pub struct OzCompatTree {
+    leaves: Vec<[u8; 32]>,
+}
+
+impl OzCompatTree {
+    pub fn new() -> Self {
+        Self { leaves: Vec::new() }
+    }
+
+    /// Number of leaves in the tree
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Appends a leaf. Fails once the tree holds `MAX_LEAVES` leaves.
+    pub fn add_leaf(&mut self, leaf: [u8; 32]) -> Result<(), TreeError> {
+        if self.leaves.len() >= MAX_LEAVES {
+            return Err(TreeError::CapacityExceeded);
+        }
+        self.leaves.push(leaf);
+        Ok(())
+    }
+
+    /// Every level from the leaves up to the root
+    fn levels(&self) -> Vec<Vec<[u8; 32]>> {
+        let mut levels = vec![self.leaves.clone()];
+        while levels.last().is_some_and(|level| level.len() > 1) {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => oz_hash_pair(left, right),
+                    [lone] => *lone,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        levels
+    }
+
+    /// Root of the tree (all zero when empty); a single leaf is its own root
+    pub fn root(&self) -> [u8; 32] {
+        self.levels()
+            .last()
+            .and_then(|level| level.first().copied())
+            .unwrap_or([0u8; 32])
+    }
+
+    /// Proof for `leaf` as `MerkleProof.verify(proof, root, leaf)` takes it: sibling hashes
+    /// only, leaf level first, since sorted pairs need no left/right indices
+    pub fn generate_proof(&self, leaf: [u8; 32]) -> Result<Vec<[u8; 32]>, TreeError> {
+        let mut index = self
+            .leaves
+            .iter()
+            .position(|&l| l == leaf)
+            .ok_or(TreeError::CommitmentNotFound)?;
+
+        let levels = self.levels();
+        let mut proof = Vec::new();
+        for level in &levels[..levels.len() - 1] {
+            // A carried-up node has no sibling at this level
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+
+        Ok(proof)
+    }
+}
+
+impl Default for OzCompatTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// OpenZeppelin's `MerkleProof.processProof`: folds `proof` into `leaf` and returns the root
+pub fn process_oz_proof(leaf: &[u8; 32], proof: &[[u8; 32]]) -> [u8; 32] {
+    proof
+        .iter()
+        .fold(*leaf, |node, sibling| oz_hash_pair(&node, sibling))
+}
+
+/// OpenZeppelin's commutative `keccak256` of a pair: the smaller value comes first
+fn oz_hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut hasher = Keccak256::new();
+    hasher.update(first);
+    hasher.update(second);
+    hasher.finalize().into()
+}
@@ -0,0 +1,112 @@
+//! Spent-nullifier set the guest can prove an order's nullifier is absent from
+
+use serde::{Deserialize, Serialize};
+
+use crate::{verify_commitment_merkle_proof, CommitmentMerkleTree, Hash32, MerkleProof, TreeError};
+
+/// Bounds stored in every spent-nullifier tree, so any unspent nullifier hash lies strictly
+/// between two adjacent leaves
+pub const SPENT_LOW_SENTINEL: [u8; 32] = [0u8; 32];
+pub const SPENT_HIGH_SENTINEL: [u8; 32] = [0xffu8; 32];
+
+/// A leaf of the spent-nullifier tree with its Merkle path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NeighborProof {
+    pub leaf: [u8; 32],
+    pub siblings: Vec<[u8; 32]>,
+    pub indices: Vec<u8>,
+}
+
+impl NeighborProof {
+    /// Leaf position encoded by the path (`indices[i]` is bit `i`; any non-zero index means
+    /// right, as in `compute_merkle_root`)
+    fn position(&self) -> u64 {
+        self.indices
+            .iter()
+            .enumerate()
+            .fold(0, |position, (level, &index)| {
+                position | (u64::from(index != 0) << level)
+            })
+    }
+
+    /// Whether `leaf` is in the tree with root `root`
+    fn verify(&self, root: &[u8; 32]) -> bool {
+        MerkleProof::from_parts(&self.siblings, &self.indices)
+            .is_ok_and(|proof| verify_commitment_merkle_proof(&self.leaf, &proof, &Hash32(*root)))
+    }
+}
+
+/// Proof that a nullifier hash is not in the spent-nullifier tree with root `root`: `low` and
+/// `high` are adjacent leaves with `low.leaf < nullifier_hash < high.leaf`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NonMembershipProof {
+    /// Public spent-nullifier tree root, committed as `GuestOutputs::spent_root`
+    pub root: [u8; 32],
+    pub low: NeighborProof,
+    pub high: NeighborProof,
+}
+
+/// Sorted tree of spent nullifier hashes plus the two sentinels.
+///
+/// Settlement maintains the same set on-chain; its root is what `GuestOutputs::spent_root` must
+/// match for a proof to count as replay-checked. Fails with `CapacityExceeded` past
+/// `MAX_LEAVES` leaves.
+pub fn spent_nullifier_tree(spent: &[[u8; 32]]) -> Result<CommitmentMerkleTree<()>, TreeError> {
+    let mut leaves = spent.to_vec();
+    leaves.push(SPENT_LOW_SENTINEL);
+    leaves.push(SPENT_HIGH_SENTINEL);
+    leaves.sort_unstable();
+    leaves.dedup();
+    CommitmentMerkleTree::from_sorted(&leaves)
+}
+
+/// Neighbors of `nullifier_hash` in a tree built by `spent_nullifier_tree`.
+///
+/// The result only verifies if the nullifier is unspent: for a spent one, `high` is the
+/// nullifier's own leaf and `verify_not_spent` rejects it.
+pub fn prove_not_spent(
+    tree: &CommitmentMerkleTree<()>,
+    nullifier_hash: &[u8; 32],
+) -> NonMembershipProof {
+    let leaves = tree.leaves();
+    // The sentinels guarantee 0 < high < leaves.len() for any hash other than the sentinels
+    let high = leaves
+        .partition_point(|leaf| leaf < nullifier_hash)
+        .clamp(1, leaves.len() - 1);
+    let neighbor = |leaf: [u8; 32]| {
+        let (siblings, indices) = tree
+            .generate_proof(leaf)
+            .expect("leaf taken from the tree")
+            .into_parts();
+        NeighborProof {
+            leaf,
+            siblings,
+            indices,
+        }
+    };
+
+    NonMembershipProof {
+        root: tree
+            .build_tree()
+            .expect("sentinels keep the tree non-empty")
+            .0,
+        low: neighbor(leaves[high - 1]),
+        high: neighbor(leaves[high]),
+    }
+}
+
+/// Checks that `nullifier_hash` is absent from the spent-nullifier tree `proof.root`.
+///
+/// Both neighbors must be in the tree, at adjacent positions, and strictly bracket the hash.
+/// Sorted leaves make adjacency imply nothing lies between them; the duplicated last node of an
+/// odd level only repeats a sorted run after the largest leaf, so it cannot fake an adjacent pair.
+pub fn verify_not_spent(nullifier_hash: &[u8; 32], proof: &NonMembershipProof) -> bool {
+    let NonMembershipProof { root, low, high } = proof;
+
+    low.leaf < *nullifier_hash
+        && *nullifier_hash < high.leaf
+        && low.indices.len() == high.indices.len()
+        && low.position() + 1 == high.position()
+        && low.verify(root)
+        && high.verify(root)
+}
@@ -0,0 +1,41 @@
+//! Canonical guest stdin for hosts
+
+use sp1_sdk::SP1Stdin;
+
+use crate::{InputError, OrderProofInputs};
+
+/// Validates `inputs` and writes them in the exact order the guest reads them.
+///
+/// Every host builds stdin through this function; the guest reads positionally, so writing the
+/// fields in any other order yields garbage inputs rather than an error. Nothing is written if
+/// validation fails.
+pub fn build_stdin(inputs: &OrderProofInputs) -> Result<SP1Stdin, InputError> {
+    inputs.validate()?;
+
+    let mut stdin = SP1Stdin::new();
+
+    // Public inputs
+    stdin.write(&inputs.market);
+    stdin.write(&inputs.merkle_root);
+    stdin.write(&inputs.nullifier_hash);
+    stdin.write(&inputs.domain);
+    stdin.write(&inputs.disclosure);
+    stdin.write(&inputs.nullifier_bits);
+    stdin.write(&inputs.fee_recipient);
+    stdin.write(&inputs.cross_check);
+
+    // Private inputs
+    stdin.write(&inputs.order);
+    stdin.write(&inputs.nullifier);
+    stdin.write(&inputs.balance);
+    stdin.write(&inputs.siblings);
+    stdin.write(&inputs.indices);
+    stdin.write(&inputs.blinding);
+    stdin.write(&inputs.metadata_hash);
+    stdin.write(&inputs.balance_proof);
+    stdin.write(&inputs.spent_proof);
+    stdin.write(&inputs.balance_blinding);
+    stdin.write(&inputs.price_proof);
+
+    Ok(stdin)
+}
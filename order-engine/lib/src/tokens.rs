@@ -0,0 +1,132 @@
+//! Per-token decimals and quote scales, for normalizing quoted prices to raw `PRICE_SCALE` units
+//!
+//! Host-side configuration only: nothing here is committed or read by the guest, which sees the
+//! normalized `MarketConditions`.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{MarketConditions, MarketConditionsBuilder, MarketError, TokenAddress, PRICE_SCALE};
+
+/// How one token's amounts and prices are denominated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenConfig {
+    /// ERC-20 decimals of the token's base unit
+    pub decimals: u8,
+    /// Decimal places of prices quoted for this token as token_in: a quoted price `q` means
+    /// `q / 10^quote_decimals` whole token_out per whole token. Micro-caps need more places than
+    /// majors to keep precision.
+    pub quote_decimals: u8,
+}
+
+/// `TokenConfig` by token address
+#[derive(Debug, Clone, Default)]
+pub struct TokenRegistry {
+    tokens: BTreeMap<TokenAddress, TokenConfig>,
+}
+
+impl TokenRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Registers `token`, returning its previous config if it had one
+    pub fn insert(&mut self, token: TokenAddress, config: TokenConfig) -> Option<TokenConfig> {
+        self.tokens.insert(token, config)
+    }
+
+    pub fn get(&self, token: &TokenAddress) -> Option<&TokenConfig> {
+        self.tokens.get(token)
+    }
+
+    /// Configs of both sides of a pair; `UnknownToken` if either is not registered
+    fn pair(
+        &self,
+        token_in: &TokenAddress,
+        token_out: &TokenAddress,
+    ) -> Result<(TokenConfig, TokenConfig), MarketError> {
+        match (self.get(token_in), self.get(token_out)) {
+            (Some(token_in), Some(token_out)) => Ok((*token_in, *token_out)),
+            _ => Err(MarketError::UnknownToken),
+        }
+    }
+
+    /// Raw price (token_out base units per `PRICE_SCALE` token_in base units) of a price quoted
+    /// at token_in's `quote_decimals`, rounded to the nearest unit.
+    ///
+    /// Integer-only, so it is exact wherever the raw price is. Fails with `ZeroPrice` when the
+    /// quote is below one raw unit, which no `PRICE_SCALE` price can represent.
+    pub fn normalize_price(
+        &self,
+        token_in: &TokenAddress,
+        token_out: &TokenAddress,
+        quoted_price: u64,
+    ) -> Result<u64, MarketError> {
+        let (token_in, token_out) = self.pair(token_in, token_out)?;
+        let exponent = PRICE_SCALE.ilog10() as i32 + i32::from(token_out.decimals)
+            - i32::from(token_in.decimals)
+            - i32::from(token_in.quote_decimals);
+
+        let quoted = u128::from(quoted_price);
+        let raw = if exponent >= 0 {
+            10u128
+                .checked_pow(exponent as u32)
+                .and_then(|scale| quoted.checked_mul(scale))
+                .ok_or(MarketError::PriceOverflow)?
+        } else {
+            // A divisor past u128 leaves nothing of any u64 quote
+            match 10u128.checked_pow(exponent.unsigned_abs()) {
+                Some(divisor) => (quoted + divisor / 2) / divisor,
+                None => 0,
+            }
+        };
+
+        if raw == 0 {
+            return Err(MarketError::ZeroPrice);
+        }
+        u64::try_from(raw).map_err(|_| MarketError::PriceOverflow)
+    }
+
+    /// `MarketConditions` for a pair from quoted current and reference prices (0 for no
+    /// reference)
+    pub fn market(
+        &self,
+        token_in: &TokenAddress,
+        token_out: &TokenAddress,
+        quoted_price: u64,
+        quoted_reference_price: u64,
+        block_timestamp: u64,
+    ) -> Result<MarketConditions, MarketError> {
+        if block_timestamp == 0 {
+            return Err(MarketError::MissingTimestamp);
+        }
+
+        Ok(MarketConditions {
+            current_price: self.normalize_price(token_in, token_out, quoted_price)?,
+            block_timestamp,
+            reference_price: match quoted_reference_price {
+                0 => 0,
+                price => self.normalize_price(token_in, token_out, price)?,
+            },
+        })
+    }
+
+    /// `MarketConditions::builder()` with the pair's decimals, for whole-token prices
+    pub fn builder(
+        &self,
+        token_in: &TokenAddress,
+        token_out: &TokenAddress,
+    ) -> Result<MarketConditionsBuilder, MarketError> {
+        let (token_in, token_out) = self.pair(token_in, token_out)?;
+        Ok(MarketConditions::builder().decimals(token_in.decimals, token_out.decimals))
+    }
+}
@@ -0,0 +1,419 @@
+//! Commitment Merkle tree maintained by relayers (script demo and server)
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cell::OnceCell;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::{Hash32, HashConfig, InputError, MAX_TREE_DEPTH};
+
+/// Most leaves a tree can hold while every proof stays within `MAX_TREE_DEPTH`
+pub const MAX_LEAVES: usize = 1 << MAX_TREE_DEPTH;
+
+/// Every level of a tree from the leaves up to the root
+pub type TreeLevels = Vec<Vec<[u8; 32]>>;
+
+/// Why a `CommitmentMerkleTree` (or `OzCompatTree`) operation failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeError {
+    /// The commitment is not a leaf of the tree
+    CommitmentNotFound,
+    /// The tree has no leaves, so it has no root
+    EmptyTree,
+    /// The commitment is already a leaf; a second copy would make its index ambiguous
+    DuplicateCommitment,
+    /// The tree already holds `MAX_LEAVES` leaves
+    CapacityExceeded,
+}
+
+impl std::fmt::Display for TreeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TreeError::CommitmentNotFound => f.write_str("commitment not found in tree"),
+            TreeError::EmptyTree => f.write_str("commitment tree is empty"),
+            TreeError::DuplicateCommitment => f.write_str("commitment is already in the tree"),
+            TreeError::CapacityExceeded => {
+                write!(f, "commitment tree is full ({MAX_LEAVES} leaves)")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TreeError {}
+
+/// Merkle inclusion path, leaf level first, pairing each level's index with its sibling so the
+/// two can never disagree in length.
+///
+/// An index of 0 means the running node is the left child at that level. Guest inputs and JSON
+/// still carry the legacy split form (`siblings`, `indices`); convert with `from_parts` /
+/// `TryFrom` and `into_parts` / `From`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub path: Vec<(u8, [u8; 32])>,
+}
+
+impl MerkleProof {
+    /// Pairs a split path, rejecting one with more siblings than indices or the reverse
+    pub fn from_parts(siblings: &[[u8; 32]], indices: &[u8]) -> Result<Self, InputError> {
+        if siblings.len() != indices.len() {
+            return Err(InputError::PathLengthMismatch {
+                siblings: siblings.len(),
+                indices: indices.len(),
+            });
+        }
+        Ok(Self {
+            path: indices
+                .iter()
+                .copied()
+                .zip(siblings.iter().copied())
+                .collect(),
+        })
+    }
+
+    /// The legacy split form: `(siblings, indices)`
+    pub fn into_parts(self) -> (Vec<[u8; 32]>, Vec<u8>) {
+        self.path
+            .into_iter()
+            .map(|(index, sibling)| (sibling, index))
+            .unzip()
+    }
+}
+
+impl TryFrom<(Vec<[u8; 32]>, Vec<u8>)> for MerkleProof {
+    type Error = InputError;
+
+    fn try_from((siblings, indices): (Vec<[u8; 32]>, Vec<u8>)) -> Result<Self, InputError> {
+        Self::from_parts(&siblings, &indices)
+    }
+}
+
+impl From<MerkleProof> for (Vec<[u8; 32]>, Vec<u8>) {
+    fn from(proof: MerkleProof) -> Self {
+        proof.into_parts()
+    }
+}
+
+/// Merkle tree for commitments (not individual balances)
+///
+/// Each leaf carries relayer-side metadata of type `Meta` (e.g. the submitting user,
+/// submission time or deadline). The metadata never enters the tree hashes.
+pub struct CommitmentMerkleTree<Meta> {
+    leaves: Vec<[u8; 32]>,
+    metas: Vec<Meta>, // Metadata for each commitment, indexed like `leaves`
+    members: HashSet<[u8; 32]>, // The leaves, for duplicate checks
+    sorted: bool,     // Keep leaves in canonical (byte-wise ascending) order
+    levels: OnceCell<Vec<Vec<[u8; 32]>>>, // Built on first use, cleared whenever leaves change
+}
+
+impl<Meta> CommitmentMerkleTree<Meta> {
+    pub fn new() -> Self {
+        Self {
+            leaves: Vec::new(),
+            metas: Vec::new(),
+            members: HashSet::new(),
+            sorted: false,
+            levels: OnceCell::new(),
+        }
+    }
+
+    /// Number of commitments (leaves) in the tree
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Leaves in tree order
+    pub fn leaves(&self) -> &[[u8; 32]] {
+        &self.leaves
+    }
+
+    /// Adds a commitment. Insertion-ordered trees append it; sorted trees insert it at its
+    /// canonical position, which shifts the leaf indices of every larger commitment.
+    ///
+    /// Fails with `DuplicateCommitment` if the commitment is already a leaf, and with
+    /// `CapacityExceeded` once the tree holds `MAX_LEAVES` commitments.
+    pub fn add_commitment(
+        &mut self,
+        commitment_hash: [u8; 32],
+        meta: Meta,
+    ) -> Result<(), TreeError> {
+        self.check_insert(&commitment_hash)?;
+        self.members.insert(commitment_hash);
+
+        if self.sorted {
+            let index = self.leaves.partition_point(|leaf| *leaf < commitment_hash);
+            self.leaves.insert(index, commitment_hash);
+            self.metas.insert(index, meta);
+        } else {
+            self.leaves.push(commitment_hash);
+            self.metas.push(meta);
+        }
+        self.levels.take();
+
+        Ok(())
+    }
+
+    /// Returns the metadata stored alongside the given commitment, if present
+    pub fn meta_for(&self, commitment_hash: &[u8; 32]) -> Option<&Meta> {
+        self.leaves
+            .iter()
+            .position(|leaf| leaf == commitment_hash)
+            .map(|index| &self.metas[index])
+    }
+
+    /// Root and every level of the tree; `EmptyTree` when it has no leaves
+    pub fn build_tree(&self) -> Result<([u8; 32], TreeLevels), TreeError> {
+        let levels = self.levels();
+        let root = levels.last().ok_or(TreeError::EmptyTree)?[0];
+        Ok((root, levels.clone()))
+    }
+
+    /// Every level from the leaves (`levels()[0]`) up to the root; empty for an empty tree.
+    ///
+    /// Computed once and reused by `build_tree` and `generate_proof` until the next insertion.
+    pub fn levels(&self) -> &Vec<Vec<[u8; 32]>> {
+        self.levels.get_or_init(|| self.compute_levels())
+    }
+
+    /// Leaf index the next `add_commitment` lands at, the index `generate_proof` then derives
+    /// the path from. Sorted trees place a commitment at its canonical position instead, so for
+    /// them this only holds for a commitment above every current leaf.
+    pub fn next_index(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Root the tree would have after `add_commitment(new_leaf, ..)`, without changing it, so a
+    /// client can build its proof against the root it will be included under.
+    ///
+    /// Insertion-ordered trees only rehash the new leaf's path, reusing the built levels; sorted
+    /// trees rebuild from a copy with the leaf at its canonical position. Fails exactly when
+    /// the insert would.
+    pub fn preview_root_with(&self, new_leaf: [u8; 32]) -> Result<[u8; 32], TreeError> {
+        self.check_insert(&new_leaf)?;
+        if self.sorted {
+            let mut leaves = self.leaves.clone();
+            let index = leaves.partition_point(|leaf| *leaf < new_leaf);
+            leaves.insert(index, new_leaf);
+            let preview = CommitmentMerkleTree {
+                metas: vec![(); leaves.len()],
+                leaves,
+                members: HashSet::new(), // never inserted into
+                sorted: true,
+                levels: OnceCell::new(),
+            };
+            return preview.build_tree().map(|(root, _)| root);
+        }
+
+        // The new node is last on every level: its left sibling is an unchanged node of the
+        // current tree when its index is odd, and it is paired with itself otherwise. As in
+        // `compute_levels`, even a lone leaf is hashed once.
+        let levels = self.levels();
+        let mut node = new_leaf;
+        let mut index = self.leaves.len();
+        let mut width = index + 1;
+        let mut level = 0;
+        loop {
+            node = if index % 2 == 1 {
+                self.hash_pair(levels[level][index - 1], node)
+            } else {
+                self.hash_pair(node, node)
+            };
+            index /= 2;
+            width = width.div_ceil(2);
+            if width == 1 {
+                return Ok(node);
+            }
+            level += 1;
+        }
+    }
+
+    /// Why `add_commitment(commitment_hash, ..)` would fail, if it would
+    fn check_insert(&self, commitment_hash: &[u8; 32]) -> Result<(), TreeError> {
+        if self.leaves.len() >= MAX_LEAVES {
+            return Err(TreeError::CapacityExceeded);
+        }
+        if self.members.contains(commitment_hash) {
+            return Err(TreeError::DuplicateCommitment);
+        }
+        Ok(())
+    }
+
+    fn compute_levels(&self) -> Vec<Vec<[u8; 32]>> {
+        if self.leaves.is_empty() {
+            return vec![];
+        }
+
+        let mut levels = vec![self.leaves.clone()];
+        let mut current_level = self.leaves.clone();
+
+        // A lone leaf is still hashed with itself once, so every proof has at least one sibling
+        while current_level.len() > 1 || levels.len() == 1 {
+            let mut next_level = Vec::new();
+
+            for i in (0..current_level.len()).step_by(2) {
+                let left = current_level[i];
+                let right = if i + 1 < current_level.len() {
+                    current_level[i + 1]
+                } else {
+                    left
+                };
+
+                let parent = self.hash_pair(left, right);
+                next_level.push(parent);
+            }
+
+            levels.push(next_level.clone());
+            current_level = next_level;
+        }
+
+        levels
+    }
+
+    /// Graphviz DOT rendering of the tree for debugging: one node per hash (labelled with its
+    /// first four bytes, the full hash as tooltip), edges from each parent to its children, and
+    /// a dashed edge where a lone node is paired with itself
+    pub fn to_dot(&self) -> String {
+        let levels = self.levels();
+        let mut dot = String::from(
+            "digraph CommitmentMerkleTree {\n    node [shape=box, fontname=monospace];\n",
+        );
+
+        for (level, nodes) in levels.iter().enumerate() {
+            for (index, node) in nodes.iter().enumerate() {
+                let _ = writeln!(
+                    dot,
+                    "    n{level}_{index} [label=\"{}…\", tooltip=\"0x{}\"];",
+                    hex_prefix(node),
+                    hex_full(node)
+                );
+            }
+        }
+
+        for (level, nodes) in levels.iter().enumerate().skip(1) {
+            let children = &levels[level - 1];
+            for index in 0..nodes.len() {
+                let left = 2 * index;
+                let _ = writeln!(dot, "    n{level}_{index} -> n{}_{left};", level - 1);
+                if left + 1 < children.len() {
+                    let _ = writeln!(dot, "    n{level}_{index} -> n{}_{};", level - 1, left + 1);
+                } else {
+                    let _ = writeln!(
+                        dot,
+                        "    n{level}_{index} -> n{}_{left} [style=dashed];",
+                        level - 1
+                    );
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Inclusion path of `commitment_hash`; `CommitmentNotFound` if it is not a leaf
+    pub fn generate_proof(&self, commitment_hash: [u8; 32]) -> Result<MerkleProof, TreeError> {
+        let leaf_index = self
+            .leaves
+            .iter()
+            .position(|&leaf| leaf == commitment_hash)
+            .ok_or(TreeError::CommitmentNotFound)?;
+
+        let levels = self.levels();
+        let mut path = Vec::new();
+        let mut current_index = leaf_index;
+
+        for level in &levels[..levels.len() - 1] {
+            let sibling_index = if current_index % 2 == 0 {
+                current_index + 1
+            } else {
+                current_index - 1
+            };
+
+            let sibling = if sibling_index < level.len() {
+                level[sibling_index]
+            } else {
+                level[current_index]
+            };
+
+            path.push(((current_index % 2) as u8, sibling));
+            current_index /= 2;
+        }
+
+        Ok(MerkleProof { path })
+    }
+
+    fn hash_pair(&self, left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(HashConfig::DEFAULT.merkle_node);
+        hasher.update(left);
+        hasher.update(right);
+        hasher.finalize().into()
+    }
+}
+
+impl<Meta: Default> CommitmentMerkleTree<Meta> {
+    /// Builds a tree whose leaves are sorted canonically, so every relayer holding the same
+    /// commitment set computes the same root regardless of insertion order.
+    ///
+    /// Later `add_commitment` calls keep the leaves sorted; proofs from `generate_proof` always
+    /// reflect the sorted positions.
+    ///
+    /// Fails with `CapacityExceeded` if `commitments` has more than `MAX_LEAVES` entries, and
+    /// with `DuplicateCommitment` if any appears twice.
+    pub fn from_sorted(commitments: &[[u8; 32]]) -> Result<Self, TreeError> {
+        if commitments.len() > MAX_LEAVES {
+            return Err(TreeError::CapacityExceeded);
+        }
+
+        let mut leaves = commitments.to_vec();
+        leaves.sort_unstable();
+        if leaves.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Err(TreeError::DuplicateCommitment);
+        }
+
+        Ok(Self {
+            metas: leaves.iter().map(|_| Meta::default()).collect(),
+            members: leaves.iter().copied().collect(),
+            leaves,
+            sorted: true,
+            levels: OnceCell::new(),
+        })
+    }
+}
+
+impl<Meta> Default for CommitmentMerkleTree<Meta> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether `leaves`, in this order, build a tree (by the rules of `build_tree`) whose root is
+/// `claimed_root`, e.g. to check a leaf set received from a peer relayer before trusting it.
+/// Empty leaf sets, which have no root, and sets larger than `MAX_LEAVES` never verify.
+pub fn verify_tree_root(leaves: &[[u8; 32]], claimed_root: &Hash32) -> bool {
+    if leaves.len() > MAX_LEAVES {
+        return false;
+    }
+
+    let tree = CommitmentMerkleTree {
+        leaves: leaves.to_vec(),
+        metas: vec![(); leaves.len()],
+        members: HashSet::new(), // never inserted into
+        sorted: false,
+        levels: OnceCell::new(),
+    };
+    tree.build_tree()
+        .is_ok_and(|(root, _)| root == claimed_root.0)
+}
+
+fn hex_prefix(hash: &[u8; 32]) -> String {
+    hash[..4].iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn hex_full(hash: &[u8; 32]) -> String {
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
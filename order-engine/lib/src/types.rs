@@ -0,0 +1,57 @@
+//! Newtypes for the byte arrays public functions take side by side, so a token passed as a
+//! wallet, or a leaf passed as a root, fails to compile instead of failing verification
+//!
+//! Only signatures use them. Struct fields, serialized inputs and hashing stay on the raw
+//! arrays, and each wrapper converts to and from its array with `From`/`Into`.
+
+use serde::{Deserialize, Serialize};
+
+/// Wallet address (`OrderData::wallet_address`, balance tree leaves)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+#[repr(transparent)]
+pub struct Address(pub [u8; 20]);
+
+/// ERC-20 token address (`OrderData::token_in` / `token_out`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+#[repr(transparent)]
+pub struct TokenAddress(pub [u8; 20]);
+
+/// Merkle root a proof is checked against, kept apart from the leaf hash it is checked for
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+#[repr(transparent)]
+pub struct Hash32(pub [u8; 32]);
+
+macro_rules! byte_newtype {
+    ($name:ident, $len:literal) => {
+        impl From<[u8; $len]> for $name {
+            fn from(bytes: [u8; $len]) -> Self {
+                Self(bytes)
+            }
+        }
+
+        impl From<$name> for [u8; $len] {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+    };
+}
+
+byte_newtype!(Address, 20);
+byte_newtype!(TokenAddress, 20);
+byte_newtype!(Hash32, 32);
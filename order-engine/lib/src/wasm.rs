@@ -0,0 +1,123 @@
+//! wasm-bindgen wrappers so browsers can derive commitments without sending the user secret
+//! anywhere. Build with `--target wasm32-unknown-unknown --features wasm`.
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    compute_nullifier_hash, create_order_commitment, hash_order, AmountRepr, OrderData,
+    OrderDomain, OrderKind,
+};
+
+/// Order as sent by the frontend (addresses as 0x-prefixed hex, same shape as the server API)
+#[derive(Deserialize)]
+struct OrderJs {
+    domain: DomainJs,
+    wallet_address: String,
+    token_in: String,
+    token_out: String,
+    amount_in: u64,
+    min_amount_out: u64,
+    target_price: u64,
+    deadline: u64,
+    #[serde(default)]
+    kind: OrderKind,
+    #[serde(default)]
+    max_price_impact_bps: Option<u32>,
+    #[serde(default)]
+    fee_bps: u16,
+    #[serde(default)]
+    group_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DomainJs {
+    chain_id: u64,
+    pool_address: String,
+    #[serde(default)]
+    amount_repr: AmountRepr,
+}
+
+#[derive(Serialize)]
+struct CommitmentJs {
+    order_hash: String,
+    nullifier: String, // private: keep client-side
+    nullifier_hash: String,
+    commitment_hash: String,
+}
+
+fn hex_to_array<const N: usize>(s: &str) -> Result<[u8; N], JsError> {
+    let bytes = hex::decode(s.strip_prefix("0x").unwrap_or(s))
+        .map_err(|e| JsError::new(&format!("hex decode error: {e}")))?;
+
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| JsError::new(&format!("Expected {} bytes, got {}", N, len)))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn parse_order(order_json: &str) -> Result<OrderData, JsError> {
+    let order: OrderJs =
+        serde_json::from_str(order_json).map_err(|e| JsError::new(&e.to_string()))?;
+
+    Ok(OrderData {
+        domain: OrderDomain {
+            chain_id: order.domain.chain_id,
+            pool_address: hex_to_array(&order.domain.pool_address)?,
+            amount_repr: order.domain.amount_repr,
+        },
+        wallet_address: hex_to_array(&order.wallet_address)?,
+        token_in: hex_to_array(&order.token_in)?,
+        token_out: hex_to_array(&order.token_out)?,
+        amount_in: order.amount_in,
+        min_amount_out: order.min_amount_out,
+        target_price: order.target_price,
+        deadline: order.deadline,
+        kind: order.kind,
+        max_price_impact_bps: order.max_price_impact_bps,
+        fee_bps: order.fee_bps,
+        group_id: order.group_id.as_deref().map(hex_to_array).transpose()?,
+    })
+}
+
+/// `createOrderCommitment(orderJson, secretHex, balance, contextHex)` → JSON with the order
+/// hash, the private nullifier and the public nullifier / commitment hashes
+#[wasm_bindgen(js_name = createOrderCommitment)]
+pub fn create_order_commitment_js(
+    order_json: &str,
+    secret_hex: &str,
+    balance: u64,
+    context_hex: &str,
+) -> Result<String, JsError> {
+    let order = parse_order(order_json)?;
+    let secret = hex_to_array::<32>(secret_hex)?;
+    let context = hex_to_array::<32>(context_hex)?;
+
+    let (commitment, nullifier_data) = create_order_commitment(&order, &secret, balance, &context);
+
+    serde_json::to_string(&CommitmentJs {
+        order_hash: to_hex(&hash_order(&order)),
+        nullifier: to_hex(&commitment.nullifier),
+        nullifier_hash: to_hex(&nullifier_data.nullifier_hash),
+        commitment_hash: to_hex(&nullifier_data.commitment_hash),
+    })
+    .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// `hashOrder(orderJson)` → 0x-prefixed order hash
+#[wasm_bindgen(js_name = hashOrder)]
+pub fn hash_order_js(order_json: &str) -> Result<String, JsError> {
+    Ok(to_hex(&hash_order(&parse_order(order_json)?)))
+}
+
+/// `computeNullifierHash(nullifierHex)` → 0x-prefixed public nullifier hash
+#[wasm_bindgen(js_name = computeNullifierHash)]
+pub fn compute_nullifier_hash_js(nullifier_hex: &str) -> Result<String, JsError> {
+    Ok(to_hex(&compute_nullifier_hash(&hex_to_array::<32>(
+        nullifier_hex,
+    )?)))
+}
@@ -2,9 +2,9 @@
 sp1_zkvm::entrypoint!(main);
 
 use fibonacci_lib::{
-    compute_commitment_hash, compute_nullifier_hash, validate_order,
-    verify_commitment_merkle_proof, verify_nullifier_order, MarketConditions, OrderCommitment,
-    OrderData,
+    evaluate_order_statement, BalanceProof, DisclosureLevel, GuestOutputs, MarketConditions,
+    NonMembershipProof, NullifierBits, OrderData, OrderDomain, OrderProofInputs, PriceProof,
+    MAX_TREE_DEPTH,
 };
 
 pub fn main() {
@@ -12,6 +12,11 @@ pub fn main() {
     let market_conditions = sp1_zkvm::io::read::<MarketConditions>();
     let merkle_root = sp1_zkvm::io::read::<[u8; 32]>(); // Public Merkle root
     let expected_nullifier_hash = sp1_zkvm::io::read::<[u8; 32]>(); // Public nullifier hash
+    let domain = sp1_zkvm::io::read::<OrderDomain>(); // Public deployment (chain + pool)
+    let disclosure = sp1_zkvm::io::read::<DisclosureLevel>(); // Public: which fields to commit
+    let nullifier_bits = sp1_zkvm::io::read::<NullifierBits>(); // Public: nullifier hash width
+    let fee_recipient = sp1_zkvm::io::read::<[u8; 20]>(); // Public: protocol fee recipient
+    let cross_check = sp1_zkvm::io::read::<bool>(); // Public: re-derive the statement a second way
 
     // === PRIVATE INPUTS ===
     let order_data = sp1_zkvm::io::read::<OrderData>();
@@ -19,59 +24,56 @@ pub fn main() {
     let user_balance = sp1_zkvm::io::read::<u64>(); // Private balance
     let merkle_siblings = sp1_zkvm::io::read::<Vec<[u8; 32]>>(); // Private Merkle proof
     let merkle_indices = sp1_zkvm::io::read::<Vec<u8>>(); // Private Merkle path
+    let blinding = sp1_zkvm::io::read::<Option<[u8; 32]>>(); // Private leaf blinder, if hiding
+    let metadata_hash = sp1_zkvm::io::read::<[u8; 32]>(); // Private audit metadata hash (zero if none)
+    let balance_proof = sp1_zkvm::io::read::<Option<BalanceProof>>(); // Balance tree path, if used
+    let spent_proof = sp1_zkvm::io::read::<Option<NonMembershipProof>>(); // Spent-set absence, if checked
+    let balance_blinding = sp1_zkvm::io::read::<Option<[u8; 32]>>(); // Balance blinder, if the leaf hides it
+    let price_proof = sp1_zkvm::io::read::<Option<PriceProof>>(); // Oracle price tree path, if checked
+
+    // No tree can be deeper than MAX_TREE_DEPTH, so a longer path cannot be proven at all
+    assert!(
+        merkle_siblings.len() <= MAX_TREE_DEPTH,
+        "Merkle path deeper than MAX_TREE_DEPTH"
+    );
 
-    // === NULLIFIER VERIFICATION ===
-
-    // 1. Verify nullifier hash matches expected (prevents replay attacks)
-    let computed_nullifier_hash = compute_nullifier_hash(&nullifier);
-    let nullifier_hash_valid = computed_nullifier_hash == expected_nullifier_hash;
-
-    // 2. Compute commitment hash from private inputs
-    let commitment_hash = compute_commitment_hash(&order_data, &nullifier, user_balance);
-
-    // 3. Create commitment struct for verification
-    let commitment = OrderCommitment {
-        order_data: order_data.clone(),
+    let inputs = OrderProofInputs {
+        market: market_conditions,
+        merkle_root,
+        nullifier_hash: expected_nullifier_hash,
+        domain,
+        disclosure,
+        nullifier_bits,
+        fee_recipient,
+        cross_check,
+        order: order_data,
         nullifier,
         balance: user_balance,
+        siblings: merkle_siblings,
+        indices: merkle_indices,
+        blinding,
+        metadata_hash,
+        balance_proof,
+        spent_proof,
+        balance_blinding,
+        price_proof,
     };
 
-    // === MERKLE TREE VERIFICATION ===
-
-    // 4. Verify commitment is in the Merkle tree
-    let merkle_valid = verify_commitment_merkle_proof(
-        &commitment_hash,
-        &merkle_siblings,
-        &merkle_indices,
-        &merkle_root,
-    );
+    // === STATEMENT VERIFICATION ===
 
-    // === ORDER VALIDATION ===
+    // Nullifier, domain, Merkle inclusion and order conditions, evaluated by the same reference
+    // function relayers and auditors run natively
+    let statement = evaluate_order_statement(&inputs);
 
-    // 5. Verify order execution conditions
-    let order_executable = verify_nullifier_order(
-        &commitment,
-        &market_conditions,
-        &commitment_hash,
-        &expected_nullifier_hash,
+    // A cross-checked proof only exists when both derivations of the statement agree
+    assert!(
+        statement.cross_check_ok != Some(false),
+        "statement cross-check failed"
     );
 
-    // === FINAL VERIFICATION ===
-
-    let final_validity = nullifier_hash_valid && merkle_valid && order_executable;
-
     // === PUBLIC OUTPUTS ===
 
-    // Commit the validity result
-    sp1_zkvm::io::commit(&final_validity);
-
-    // Commit the nullifier hash (to be stored on-chain for replay prevention)
-    sp1_zkvm::io::commit(&computed_nullifier_hash);
-
-    // Commit the wallet address (for order execution)
-    sp1_zkvm::io::commit(&order_data.wallet_address);
-
-    // Commit order amounts (for swap execution)
-    sp1_zkvm::io::commit(&order_data.amount_in);
-    sp1_zkvm::io::commit(&order_data.min_amount_out);
+    // Validity, nullifier hash (stored on-chain for replay prevention), metadata presence and,
+    // at the `Full` disclosure level, the settlement fields, committed as one value
+    sp1_zkvm::io::commit(&GuestOutputs::new(&inputs, &statement));
 }
@@ -0,0 +1,85 @@
+//! Guest cost measurements
+
+use fibonacci_lib::{
+    build_stdin, compute_merkle_root, create_order_commitment, hash_order, DisclosureLevel,
+    MarketConditions, NullifierBits, OrderData, OrderKind, OrderProofInputs, MAX_TREE_DEPTH,
+};
+use sp1_sdk::EnvProver;
+use std::error::Error;
+
+use crate::{DEMO_DOMAIN, DEMO_FEE_RECIPIENT};
+
+/// Executes the guest on a fixed, executable order once per Merkle depth in `1..=max_depth` and
+/// returns `(depth, cycles)` for each run.
+///
+/// Each path is synthetic (fixed siblings, always the left child) but folds to the root passed
+/// as the public input, so every run takes the full verification path.
+pub fn merkle_depth_cycles(
+    client: &EnvProver,
+    elf: &[u8],
+    max_depth: usize,
+) -> Result<Vec<(usize, u64)>, Box<dyn Error>> {
+    if max_depth > MAX_TREE_DEPTH {
+        return Err(
+            format!("max depth {max_depth} exceeds MAX_TREE_DEPTH ({MAX_TREE_DEPTH})").into(),
+        );
+    }
+
+    let secret = [1u8; 32];
+    let balance = 10000000000000000000u64;
+    let order = OrderData {
+        domain: DEMO_DOMAIN,
+        wallet_address: [1u8; 20],
+        token_in: [0xAu8; 20],
+        token_out: [0xBu8; 20],
+        amount_in: 5000000000000000000u64,
+        min_amount_out: 10000000000u64,
+        target_price: 2000000000u64,
+        deadline: 1735689600u64,
+        kind: OrderKind::ExactIn,
+        max_price_impact_bps: None,
+        fee_bps: 0,
+        group_id: None,
+    };
+    let market = MarketConditions {
+        current_price: 2050000000u64,
+        block_timestamp: 1735600000u64,
+        reference_price: 0,
+    };
+
+    let (commitment, nullifier_data) =
+        create_order_commitment(&order, &secret, balance, &hash_order(&order));
+
+    (1..=max_depth)
+        .map(|depth| {
+            let siblings: Vec<[u8; 32]> = (0..depth).map(|level| [level as u8 + 1; 32]).collect();
+            let indices = vec![0u8; depth];
+            let root = compute_merkle_root(&nullifier_data.commitment_hash, &siblings, &indices);
+
+            let stdin = build_stdin(&OrderProofInputs {
+                market: market.clone(),
+                merkle_root: root,
+                nullifier_hash: nullifier_data.nullifier_hash,
+                domain: DEMO_DOMAIN,
+                disclosure: DisclosureLevel::Full,
+                nullifier_bits: NullifierBits::Bits256,
+                fee_recipient: DEMO_FEE_RECIPIENT,
+                cross_check: false,
+                order: order.clone(),
+                nullifier: commitment.nullifier,
+                balance,
+                siblings,
+                indices,
+                blinding: None,
+                metadata_hash: [0u8; 32],
+                balance_proof: None,
+                spent_proof: None,
+                balance_blinding: None,
+                price_proof: None,
+            })?;
+
+            let (_, report) = client.execute(elf, &stdin).run()?;
+            Ok((depth, report.total_instruction_count()))
+        })
+        .collect()
+}
@@ -8,129 +8,282 @@
 //!
 //! Usage:
 //! ```shell
-//! RUST_LOG=info cargo run --release -- --execute --demo nullifier-flow
+//! RUST_LOG=info cargo run --release -- demo
+//! RUST_LOG=info cargo run --release -- demo --name matching
+//! RUST_LOG=info cargo run --release -- demo --export-fixtures fixtures/
+//! RUST_LOG=info cargo run --release -- execute --amount-in 5000000000000000000
+//! RUST_LOG=info cargo run --release -- execute --replay
+//! RUST_LOG=info cargo run --release -- prove
+//! cargo run --release -- commit --wallet 0x.. --token-in 0x.. --token-out 0x.. --secret 0x..
+//! RUST_LOG=info cargo run --release -- --help
 //! ```
 
-use alloy_sol_types::SolType;
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use fibonacci_lib::{
-    compute_nullifier_hash, create_order_commitment, hash_order, verify_commitment_merkle_proof,
-    verify_nullifier_order, MarketConditions, NullifierData, OrderCommitment, OrderData,
+    bind_metadata, blind_commitment, build_stdin, compute_balance_hiding_commitment,
+    create_order_commitment, evaluate_order_statement, expected_output, hash_order,
+    prove_not_spent, spent_nullifier_tree, verify_commitment_merkle_proof, verify_nullifier_order,
+    AmountRepr, CommitmentMerkleTree, DisclosureLevel, GuestOutputs, Hash32, MarketConditions,
+    NullifierBits, NullifierData, OrderCommitment, OrderData, OrderDomain, OrderKind,
+    OrderProofInputs, PRICE_SCALE,
 };
-use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use fibonacci_script::{
+    bench::merkle_depth_cycles,
+    demo_block_timestamp, demo_deadline,
+    fixtures::{DemoFixtures, ProofFixture, TreeFixture, UserFixture},
+    matching::{match_orders, BookOrder},
+    output_path, DEMO_DOMAIN, DEMO_FEE_RECIPIENT,
+};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
-use sp1_sdk::{include_elf, ProverClient, SP1Stdin};
+use sp1_sdk::{include_elf, HashableKey, ProverClient, SP1Stdin};
 
 /// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
 pub const FIBONACCI_ELF: &[u8] = include_elf!("fibonacci-program");
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
-struct Args {
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Walk through a dark pool scenario without running the zkVM
+    Demo {
+        #[arg(long, default_value = "nullifier-flow")]
+        name: String,
+
+        /// Write the nullifier-flow demo's final tree, commitments and proofs as JSON into DIR
+        #[arg(long, value_name = "DIR")]
+        export_fixtures: Option<PathBuf>,
+    },
+    /// Execute the guest on an order (no proof)
+    Execute {
+        #[command(flatten)]
+        order: OrderArgs,
+
+        /// Execute twice and exit non-zero unless both runs commit byte-identical outputs
+        #[arg(long)]
+        check_determinism: bool,
+    },
+    /// Generate a Groth16 proof for an order
+    Prove(OrderArgs),
+    /// Execute the guest repeatedly and report cycles and wall time
+    Bench {
+        #[command(flatten)]
+        order: OrderArgs,
+
+        #[arg(long, default_value_t = 5)]
+        iterations: u32,
+
+        /// Instead, report guest cycles for every Merkle depth from 1 to MAX_DEPTH
+        #[arg(long, value_name = "MAX_DEPTH", num_args = 0..=1, default_missing_value = "16")]
+        bench_depth: Option<usize>,
+    },
+    /// Prove an order and write the settlement contract calldata to a file
+    ExportCalldata {
+        #[command(flatten)]
+        order: OrderArgs,
+
+        #[arg(long, value_enum, default_value = "groth16")]
+        system: ProofSystem,
+
+        /// `{vkey}` and `{timestamp}` are filled in; missing directories are created
+        #[arg(long, default_value = "calldata.json")]
+        output: String,
+    },
+    /// Print the program's vkey hash; with --expect, exit non-zero if it differs
+    Vkey {
+        /// Expected 0x-prefixed vkey hash (e.g. the one pinned by clients)
+        #[arg(long, value_name = "HEX")]
+        expect: Option<String>,
+    },
+    /// Print an order's commitment and nullifier as hex JSON, to check other implementations
+    Commit(CommitArgs),
+}
+
+/// Order, secret and balance the `commit` subcommand derives a commitment from
+#[derive(Args, Debug, Clone)]
+struct CommitArgs {
+    /// 20-byte hex wallet address
+    #[arg(long, value_name = "HEX")]
+    wallet: String,
+
+    /// 20-byte hex token address
+    #[arg(long, value_name = "HEX")]
+    token_in: String,
+
+    /// 20-byte hex token address
+    #[arg(long, value_name = "HEX")]
+    token_out: String,
+
+    #[arg(long)]
+    amount_in: u64,
+
     #[arg(long)]
-    execute: bool,
+    min_amount_out: u64,
 
     #[arg(long)]
-    prove: bool,
+    target_price: u64,
+
+    #[arg(long)]
+    deadline: u64,
+
+    /// Make the order exact-out: receive exactly this amount, spending at most --max-amount-in
+    #[arg(long, requires = "max_amount_in")]
+    exact_amount_out: Option<u64>,
 
-    #[arg(long, default_value = "nullifier-flow")]
-    demo: String,
+    #[arg(long, requires = "exact_amount_out")]
+    max_amount_in: Option<u64>,
+
+    #[arg(long)]
+    max_price_impact_bps: Option<u32>,
+
+    #[arg(long, default_value_t = 0)]
+    fee_bps: u16,
+
+    /// 32-byte hex group the order settles all-or-nothing with
+    #[arg(long, value_name = "HEX")]
+    group_id: Option<String>,
+
+    /// Deployment chain (default: the demo domain's)
+    #[arg(long)]
+    chain_id: Option<u64>,
+
+    /// 20-byte hex pool address (default: the demo domain's)
+    #[arg(long, value_name = "HEX")]
+    pool_address: Option<String>,
+
+    /// Width the deployment hashes amounts at: u64 or u32
+    #[arg(long, default_value = "u64")]
+    amount_repr: AmountRepr,
+
+    /// 32-byte hex user secret the nullifier is derived from
+    #[arg(long, value_name = "HEX")]
+    secret: String,
+
+    #[arg(long)]
+    balance: u64,
+
+    /// 32-byte hex nullifier context (default: the order hash)
+    #[arg(long, value_name = "HEX")]
+    context: Option<String>,
 }
 
-/// Merkle tree for commitments (not individual balances)
-pub struct CommitmentMerkleTree {
-    leaves: Vec<[u8; 32]>,
-    users: Vec<String>, // Track which user corresponds to each commitment
+/// What `commit` prints: the same fields as the WASM `createOrderCommitment`
+#[derive(Serialize)]
+struct CommitOutput {
+    order_hash: String,
+    nullifier: String, // private: keep client-side
+    nullifier_hash: String,
+    commitment_hash: String,
 }
 
-impl CommitmentMerkleTree {
-    pub fn new() -> Self {
-        Self {
-            leaves: Vec::new(),
-            users: Vec::new(),
-        }
-    }
+/// Order, market and balance parameters (defaults reproduce Alice's demo order)
+#[derive(Args, Debug, Clone)]
+struct OrderArgs {
+    #[arg(long, default_value_t = 5000000000000000000)]
+    amount_in: u64,
 
-    pub fn add_commitment(&mut self, commitment_hash: [u8; 32], user_name: String) {
-        self.leaves.push(commitment_hash);
-        self.users.push(user_name);
-    }
+    #[arg(long, default_value_t = 10000000000)]
+    min_amount_out: u64,
 
-    pub fn build_tree(&self) -> ([u8; 32], Vec<Vec<[u8; 32]>>) {
-        if self.leaves.is_empty() {
-            return ([0u8; 32], vec![]);
-        }
+    #[arg(long, default_value_t = 2000000000)]
+    target_price: u64,
 
-        let mut levels = vec![self.leaves.clone()];
-        let mut current_level = self.leaves.clone();
+    /// Order deadline (default: `DEMO_DEADLINE`, or an hour after the market timestamp)
+    #[arg(long)]
+    deadline: Option<u64>,
 
-        while current_level.len() > 1 {
-            let mut next_level = Vec::new();
+    #[arg(long, default_value_t = 2050000000)]
+    current_price: u64,
 
-            for i in (0..current_level.len()).step_by(2) {
-                let left = current_level[i];
-                let right = if i + 1 < current_level.len() {
-                    current_level[i + 1]
-                } else {
-                    left
-                };
+    /// Market timestamp (default: `DEMO_BLOCK_TIMESTAMP`, or now)
+    #[arg(long)]
+    block_timestamp: Option<u64>,
 
-                let parent = self.hash_pair(left, right);
-                next_level.push(parent);
-            }
+    /// Oracle reference price checked by --max-price-impact-bps (0 = none)
+    #[arg(long, default_value_t = 0)]
+    reference_price: u64,
 
-            levels.push(next_level.clone());
-            current_level = next_level;
-        }
+    /// Reject execution when the market price deviates from --reference-price by more than this
+    #[arg(long)]
+    max_price_impact_bps: Option<u32>,
 
-        let root = current_level[0];
-        (root, levels)
-    }
+    /// Protocol fee taken from the gross output, in basis points
+    #[arg(long, default_value_t = 0)]
+    fee_bps: u16,
 
-    pub fn generate_proof(
-        &self,
-        commitment_hash: [u8; 32],
-    ) -> Result<(Vec<[u8; 32]>, Vec<u8>), Box<dyn Error>> {
-        let leaf_index = self
-            .leaves
-            .iter()
-            .position(|&leaf| leaf == commitment_hash)
-            .ok_or("Commitment not found in tree")?;
-
-        let (_, levels) = self.build_tree();
-        let mut siblings = Vec::new();
-        let mut indices = Vec::new();
-        let mut current_index = leaf_index;
-
-        for level in 0..(levels.len() - 1) {
-            let sibling_index = if current_index % 2 == 0 {
-                current_index + 1
-            } else {
-                current_index - 1
-            };
+    #[arg(long, default_value_t = 10000000000000000000)]
+    balance: u64,
 
-            if sibling_index < levels[level].len() {
-                siblings.push(levels[level][sibling_index]);
-            } else {
-                siblings.push(levels[level][current_index]);
-            }
+    /// Which fields the guest commits: `full` or `minimal` (valid + nullifier hash only)
+    #[arg(long, default_value = "full")]
+    disclosure: DisclosureLevel,
 
-            indices.push((current_index % 2) as u8);
-            current_index /= 2;
-        }
+    /// 32-byte hex blinder; commits a hiding leaf instead of the plain commitment hash
+    #[arg(long, value_name = "HEX")]
+    blinding: Option<String>,
 
-        Ok((siblings, indices))
-    }
+    /// 32-byte hex blinder; the commitment binds the blinded balance instead of the raw one
+    #[arg(long, value_name = "HEX")]
+    balance_blinding: Option<String>,
 
-    fn hash_pair(&self, left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
-        let mut hasher = Sha256::new();
-        hasher.update(b"MERKLE_NODE");
-        hasher.update(&left);
-        hasher.update(&right);
-        hasher.finalize().into()
-    }
+    /// 32-byte hex group; orders sharing it settle all-or-nothing
+    #[arg(long, value_name = "HEX")]
+    group_id: Option<String>,
+
+    /// Make the order exact-out: receive exactly this amount, spending at most --max-amount-in
+    /// (--amount-in and --min-amount-out are then ignored)
+    #[arg(long, requires = "max_amount_in")]
+    exact_amount_out: Option<u64>,
+
+    #[arg(long, requires = "exact_amount_out")]
+    max_amount_in: Option<u64>,
+
+    /// 32-byte hex hash of off-chain audit metadata to bind into the commitment
+    #[arg(long, value_name = "HEX")]
+    metadata_hash: Option<String>,
+
+    /// 32-byte hex nullifier hash already spent (repeatable); the guest then proves this
+    /// order's nullifier is not in the spent set
+    #[arg(long = "spent-nullifier", value_name = "HEX")]
+    spent_nullifiers: Vec<String>,
+
+    /// Mark this order's own nullifier as spent, replaying it against the guest's spent-set check
+    #[arg(long)]
+    replay: bool,
+
+    /// Width of the published nullifier hash: 256, or 128 for `uint128` storage
+    #[arg(long, env = "NULLIFIER_BITS", default_value = "256")]
+    nullifier_bits: NullifierBits,
+
+    /// Re-derive the statement a second way in the guest and report whether both agree
+    /// (doubles the order checks; meant for `execute`)
+    #[arg(long)]
+    cross_check: bool,
+}
+
+/// Enum representing the available EVM proof systems
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+enum ProofSystem {
+    Plonk,
+    Groth16,
+}
+
+/// Calldata for submitting a proof to the settlement contract
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Calldata {
+    vkey: String,
+    public_values: String,
+    proof: String,
 }
 
 /// User with their secret and orders
@@ -167,10 +320,13 @@ impl User {
     }
 }
 
-fn demonstrate_nullifier_flow() -> Result<(), Box<dyn Error>> {
+fn demonstrate_nullifier_flow(export_fixtures: Option<&Path>) -> Result<(), Box<dyn Error>> {
     println!("🎯 NULLIFIER-BASED DARK POOL FLOW");
     println!("═══════════════════════════════════════");
 
+    let block_timestamp = demo_block_timestamp()?;
+    let deadline = demo_deadline(block_timestamp)?;
+
     // Step 1: Create users with their secrets
     println!("\n👤 Step 1: Creating Users with Secrets");
 
@@ -186,23 +342,33 @@ fn demonstrate_nullifier_flow() -> Result<(), Box<dyn Error>> {
     println!("\n📝 Step 2: Users Create Orders");
 
     let alice_order = OrderData {
+        domain: DEMO_DOMAIN,
         wallet_address: [1u8; 20],
         token_in: [0xAu8; 20],             // ETH
         token_out: [0xBu8; 20],            // USDC
         amount_in: 5000000000000000000u64, // 5 ETH
         min_amount_out: 10000000000u64,    // 10k USDC
         target_price: 2000000000u64,       // $2000/ETH
-        deadline: 1735689600u64,
+        deadline,
+        kind: OrderKind::ExactIn,
+        max_price_impact_bps: None,
+        fee_bps: 0,
+        group_id: None,
     };
 
     let bob_order = OrderData {
+        domain: DEMO_DOMAIN,
         wallet_address: [2u8; 20],
         token_in: [0xBu8; 20],                  // USDC
         token_out: [0xAu8; 20],                 // ETH
         amount_in: 8000000000u64,               // 8k USDC
         min_amount_out: 3800000000000000000u64, // 3.8 ETH
         target_price: 2100000000u64,            // Max $2100/ETH
-        deadline: 1735689600u64,
+        deadline,
+        kind: OrderKind::ExactIn,
+        max_price_impact_bps: None,
+        fee_bps: 0,
+        group_id: None,
     };
 
     // Create commitments
@@ -232,11 +398,11 @@ fn demonstrate_nullifier_flow() -> Result<(), Box<dyn Error>> {
     // Step 3: Build commitment Merkle tree
     println!("\n🌳 Step 3: Building Commitment Tree");
 
-    let mut commitment_tree = CommitmentMerkleTree::new();
-    commitment_tree.add_commitment(alice_nullifier.commitment_hash, alice.name.clone());
-    commitment_tree.add_commitment(bob_nullifier.commitment_hash, bob.name.clone());
+    let mut commitment_tree = CommitmentMerkleTree::<String>::new();
+    commitment_tree.add_commitment(alice_nullifier.commitment_hash, alice.name.clone())?;
+    commitment_tree.add_commitment(bob_nullifier.commitment_hash, bob.name.clone())?;
 
-    let (tree_root_v1, _) = commitment_tree.build_tree();
+    let (tree_root_v1, _) = commitment_tree.build_tree()?;
     println!("  Tree v1 Root: {:02x?}", &tree_root_v1[..8]);
     println!("  Commitments: Alice, Bob");
 
@@ -244,20 +410,25 @@ fn demonstrate_nullifier_flow() -> Result<(), Box<dyn Error>> {
     println!("\n👥 Step 4: Charlie Joins (New User)");
 
     let charlie_order = OrderData {
+        domain: DEMO_DOMAIN,
         wallet_address: [3u8; 20],
         token_in: [0xBu8; 20],                  // USDC
         token_out: [0xAu8; 20],                 // ETH
         amount_in: 3000000000u64,               // 3k USDC
         min_amount_out: 1400000000000000000u64, // 1.4 ETH
         target_price: 2150000000u64,            // Max $2150/ETH
-        deadline: 1735689600u64,
+        deadline,
+        kind: OrderKind::ExactIn,
+        max_price_impact_bps: None,
+        fee_bps: 0,
+        group_id: None,
     };
 
     let (charlie_commitment, charlie_nullifier) = charlie.create_order(charlie_order);
 
     // Add Charlie to the tree
-    commitment_tree.add_commitment(charlie_nullifier.commitment_hash, charlie.name.clone());
-    let (tree_root_v2, _) = commitment_tree.build_tree();
+    commitment_tree.add_commitment(charlie_nullifier.commitment_hash, charlie.name.clone())?;
+    let (tree_root_v2, _) = commitment_tree.build_tree()?;
 
     println!("  Charlie order: 3k USDC → ETH at max $2150");
     println!("  Tree v2 Root: {:02x?}", &tree_root_v2[..8]);
@@ -269,26 +440,21 @@ fn demonstrate_nullifier_flow() -> Result<(), Box<dyn Error>> {
 
     let market_conditions = MarketConditions {
         current_price: 2050000000u64, // $2050 (favorable for Alice)
-        block_timestamp: 1735600000u64,
+        block_timestamp,
+        reference_price: 0,
     };
 
     // Generate proof for Alice's commitment in the NEW tree
-    let (alice_siblings, alice_indices) =
-        commitment_tree.generate_proof(alice_nullifier.commitment_hash)?;
+    let alice_proof = commitment_tree.generate_proof(alice_nullifier.commitment_hash)?;
 
     println!("  Market price: $2050 (Alice's target: $2000) ✅");
-    println!(
-        "  Alice's proof: {} siblings, {} indices",
-        alice_siblings.len(),
-        alice_indices.len()
-    );
+    println!("  Alice's proof: {} levels", alice_proof.path.len());
 
     // Verify Alice's order can execute
     let alice_merkle_valid = verify_commitment_merkle_proof(
         &alice_nullifier.commitment_hash,
-        &alice_siblings,
-        &alice_indices,
-        &tree_root_v2, // Using NEW tree root!
+        &alice_proof,
+        &Hash32(tree_root_v2), // Using NEW tree root!
     );
 
     let alice_order_valid = verify_nullifier_order(
@@ -335,16 +501,21 @@ fn demonstrate_nullifier_flow() -> Result<(), Box<dyn Error>> {
 
     // Create their orders and add to tree
     let diana_order = OrderData {
+        domain: DEMO_DOMAIN,
         wallet_address: [4u8; 20],
         token_in: [0xAu8; 20],             // ETH
         token_out: [0xBu8; 20],            // USDC
         amount_in: 8000000000000000000u64, // 8 ETH
         min_amount_out: 16000000000u64,    // 16k USDC
         target_price: 2000000000u64,       // $2000/ETH
-        deadline: 1735689600u64,
+        deadline,
+        kind: OrderKind::ExactIn,
+        max_price_impact_bps: None,
+        fee_bps: 0,
+        group_id: None,
     };
 
-    let (_, diana_nullifier) = create_order_commitment(
+    let (diana_commitment, diana_nullifier) = create_order_commitment(
         &diana_order,
         &diana.secret,
         diana.balance,
@@ -352,38 +523,41 @@ fn demonstrate_nullifier_flow() -> Result<(), Box<dyn Error>> {
     );
 
     let eve_order = OrderData {
+        domain: DEMO_DOMAIN,
         wallet_address: [5u8; 20],
         token_in: [0xBu8; 20],                  // USDC
         token_out: [0xAu8; 20],                 // ETH
         amount_in: 12000000000u64,              // 12k USDC
         min_amount_out: 5500000000000000000u64, // 5.5 ETH
         target_price: 2200000000u64,            // Max $2200/ETH
-        deadline: 1735689600u64,
+        deadline,
+        kind: OrderKind::ExactIn,
+        max_price_impact_bps: None,
+        fee_bps: 0,
+        group_id: None,
     };
 
-    let (_, eve_nullifier) = create_order_commitment(
+    let (eve_commitment, eve_nullifier) = create_order_commitment(
         &eve_order,
         &eve.secret,
         eve.balance,
         &hash_order(&eve_order),
     );
 
-    commitment_tree.add_commitment(diana_nullifier.commitment_hash, "Diana".to_string());
-    commitment_tree.add_commitment(eve_nullifier.commitment_hash, "Eve".to_string());
+    commitment_tree.add_commitment(diana_nullifier.commitment_hash, "Diana".to_string())?;
+    commitment_tree.add_commitment(eve_nullifier.commitment_hash, "Eve".to_string())?;
 
-    let (tree_root_v3, _) = commitment_tree.build_tree();
+    let (tree_root_v3, _) = commitment_tree.build_tree()?;
     println!("  Added Diana (10 ETH) and Eve (25k USDC)");
     println!("  Tree v3 Root: {:02x?}", &tree_root_v3[..8]);
     println!("  Total users: Alice, Bob, Charlie, Diana, Eve");
 
     // Verify Bob can still execute against the newest tree
-    let (bob_siblings_v3, bob_indices_v3) =
-        commitment_tree.generate_proof(bob_nullifier.commitment_hash)?;
+    let bob_proof_v3 = commitment_tree.generate_proof(bob_nullifier.commitment_hash)?;
     let bob_still_valid = verify_commitment_merkle_proof(
         &bob_nullifier.commitment_hash,
-        &bob_siblings_v3,
-        &bob_indices_v3,
-        &tree_root_v3,
+        &bob_proof_v3,
+        &Hash32(tree_root_v3),
     );
 
     println!("  Bob's order still executable: {}", bob_still_valid);
@@ -396,83 +570,533 @@ fn demonstrate_nullifier_flow() -> Result<(), Box<dyn Error>> {
     println!("  ✅ Scalable - tree can grow indefinitely");
     println!("  ✅ Independent execution - orders don't interfere");
 
+    if let Some(dir) = export_fixtures {
+        let fixtures = DemoFixtures {
+            tree: TreeFixture {
+                leaves: commitment_tree.leaves().to_vec(),
+                root: tree_root_v3,
+            },
+            users: [
+                (alice.name, alice_commitment, alice_nullifier.clone()),
+                (bob.name, bob_commitment, bob_nullifier.clone()),
+                (charlie.name, charlie_commitment, charlie_nullifier),
+                (diana.name, diana_commitment, diana_nullifier),
+                (eve.name, eve_commitment, eve_nullifier),
+            ]
+            .into_iter()
+            .map(|(name, commitment, nullifier)| UserFixture {
+                name,
+                commitment,
+                nullifier,
+            })
+            .collect(),
+            proofs: vec![
+                ProofFixture {
+                    name: "Alice".to_string(),
+                    leaf: alice_nullifier.commitment_hash,
+                    root: tree_root_v2,
+                    proof: alice_proof,
+                },
+                ProofFixture {
+                    name: "Bob".to_string(),
+                    leaf: bob_nullifier.commitment_hash,
+                    root: tree_root_v3,
+                    proof: bob_proof_v3,
+                },
+            ],
+        };
+        fixtures.write(dir)?;
+        println!("\n📦 Fixtures written to {}", dir.display());
+    }
+
     Ok(())
 }
 
-fn run_sp1_nullifier_test() -> Result<(), Box<dyn Error>> {
-    println!("\n🔬 SP1 NULLIFIER TEST");
-    println!("═══════════════════════");
+/// Runs a relayer over a small book of crossing and non-crossing orders: match, execute the
+/// statement for both legs of each match at its prices and spend both nullifiers once they hold.
+fn demonstrate_matching() -> Result<(), Box<dyn Error>> {
+    println!("🤝 RELAYER MATCHING SIMULATION");
+    println!("═══════════════════════════════════════");
 
-    let client = ProverClient::from_env();
+    // Both tokens have 18 decimals and trade near 1.2 ETH per wstETH, so prices fit a u64 in
+    // both directions
+    let wsteth = [0xAu8; 20];
+    let eth = [0xBu8; 20];
+    let block_timestamp = demo_block_timestamp()?;
+    let deadline = demo_deadline(block_timestamp)?;
+    let scale = PRICE_SCALE as u64;
+
+    // Whole-order limit orders. Sells take at least `limit` ETH per wstETH; buys pay at most
+    // `limit`, i.e. take at least 1 / `limit` wstETH per ETH.
+    let order = |wallet: u8, selling: bool, amount_in: u64, limit: u64| {
+        let target_price = if selling {
+            limit
+        } else {
+            (PRICE_SCALE * PRICE_SCALE / limit as u128) as u64
+        };
+        OrderData {
+            domain: DEMO_DOMAIN,
+            wallet_address: [wallet; 20],
+            token_in: if selling { wsteth } else { eth },
+            token_out: if selling { eth } else { wsteth },
+            amount_in,
+            min_amount_out: expected_output(amount_in, target_price, 18, 18)
+                .expect("demo amounts fit in a u64"),
+            target_price,
+            deadline,
+            kind: OrderKind::ExactIn,
+            max_price_impact_bps: None,
+            fee_bps: 0,
+            group_id: None,
+        }
+    };
+    let ether = |tenths: u64| tenths * (scale / 10);
+    let limit = |thousandths: u64| thousandths * (scale / 1000);
+
+    let participants = [
+        (
+            User::new("Alice".to_string(), [1u8; 32], ether(100)),
+            order(1, true, ether(50), limit(1190)),
+        ),
+        (
+            User::new("Frank".to_string(), [6u8; 32], ether(30)),
+            order(6, true, ether(20), limit(1195)),
+        ),
+        (
+            User::new("Diana".to_string(), [4u8; 32], ether(100)),
+            order(4, true, ether(80), limit(1250)),
+        ),
+        (
+            User::new("Bob".to_string(), [2u8; 32], ether(50)),
+            order(2, false, ether(30), limit(1220)),
+        ),
+        (
+            User::new("Eve".to_string(), [5u8; 32], ether(100)),
+            order(5, false, ether(60), limit(1210)),
+        ),
+        (
+            User::new("Charlie".to_string(), [3u8; 32], ether(50)),
+            order(3, false, ether(24), limit(1205)),
+        ),
+    ];
+
+    // Step 1: Users commit their orders
+    println!("\n📝 Step 1: Users Commit Orders");
+
+    let mut tree = CommitmentMerkleTree::<String>::new();
+    let mut book = Vec::new();
+    let mut openings = HashMap::new();
+    // With the `sqlite` feature and `DEMO_ORDER_DB` set, the book and its spent nullifiers
+    // outlive the run, so orders settled by an earlier run are not matched again
+    #[cfg(feature = "sqlite")]
+    let store = fibonacci_script::demo_order_store()?;
+
+    for (mut user, order) in participants {
+        let (commitment, nullifier_data) = user.create_order(order.clone());
+
+        let selling = order.token_in == wsteth;
+        let (side, amount, limit) = if selling {
+            ("sells", "wstETH", order.target_price)
+        } else {
+            (
+                "buys with",
+                "ETH",
+                (PRICE_SCALE * PRICE_SCALE / order.target_price as u128) as u64,
+            )
+        };
+        println!(
+            "  {} {} {:.1} {} at {} {:.3} ETH per wstETH",
+            user.name,
+            side,
+            order.amount_in as f64 / scale as f64,
+            amount,
+            if selling { "≥" } else { "≤" },
+            limit as f64 / scale as f64
+        );
+
+        tree.add_commitment(nullifier_data.commitment_hash, user.name.clone())?;
+        #[cfg(feature = "sqlite")]
+        if let Some(store) = &store {
+            store.insert_order(&order, &nullifier_data)?;
+        }
+        openings.insert(
+            nullifier_data.nullifier_hash,
+            (commitment, nullifier_data.clone()),
+        );
+        book.push(BookOrder {
+            owner: user.name,
+            nullifier_hash: nullifier_data.nullifier_hash,
+            order,
+        });
+    }
+
+    let (tree_root, _) = tree.build_tree()?;
+    println!("  Tree Root: {:02x?}", &tree_root[..8]);
+
+    // Step 2: Relayer matches crossing orders whose quantities fit both limits
+    println!("\n🔀 Step 2: Matching Crossing Orders");
+
+    let mut spent = HashSet::new();
+    #[cfg(feature = "sqlite")]
+    if let Some(store) = &store {
+        for order in &book {
+            if store.is_spent(&order.nullifier_hash)? {
+                spent.insert(order.nullifier_hash);
+            }
+        }
+        println!("  Spent in earlier runs: {}", spent.len());
+    }
+    let matches = match_orders(&book, wsteth, block_timestamp, &spent);
+    let owner = |nullifier_hash: &[u8; 32]| {
+        book.iter()
+            .find(|o| o.nullifier_hash == *nullifier_hash)
+            .map(|o| o.owner.as_str())
+            .unwrap_or("?")
+    };
+    println!("  Matches found: {}", matches.len());
+
+    // Step 3: Settle each match: both legs must pass the guest statement at their prices
+    println!("\n⚡ Step 3: Settling Matches");
+
+    for m in &matches {
+        let mut legs_valid = true;
+        for (nullifier_hash, market) in [(&m.sell, &m.sell_market), (&m.buy, &m.buy_market)] {
+            let (commitment, nullifier_data) = &openings[nullifier_hash];
+            let (siblings, indices) = tree
+                .generate_proof(nullifier_data.commitment_hash)?
+                .into_parts();
+            let inputs = OrderProofInputs {
+                market: market.clone(),
+                merkle_root: tree_root,
+                nullifier_hash: *nullifier_hash,
+                domain: DEMO_DOMAIN,
+                disclosure: DisclosureLevel::Full,
+                nullifier_bits: NullifierBits::Bits256,
+                fee_recipient: DEMO_FEE_RECIPIENT,
+                cross_check: false,
+                order: commitment.order_data.clone(),
+                nullifier: commitment.nullifier,
+                balance: commitment.balance,
+                siblings,
+                indices,
+                blinding: None,
+                metadata_hash: [0u8; 32],
+                balance_proof: None,
+                spent_proof: None,
+                balance_blinding: None,
+                price_proof: None,
+            };
+            legs_valid &= evaluate_order_statement(&inputs).valid;
+        }
+
+        println!(
+            "  {} sells to {} at {:.4} ETH per wstETH (both legs valid: {})",
+            owner(&m.sell),
+            owner(&m.buy),
+            m.sell_market.current_price as f64 / scale as f64,
+            legs_valid
+        );
+        if legs_valid {
+            spent.insert(m.sell);
+            spent.insert(m.buy);
+            #[cfg(feature = "sqlite")]
+            if let Some(store) = &store {
+                store.mark_spent(&m.sell)?;
+                store.mark_spent(&m.buy)?;
+            }
+            println!(
+                "    Nullifiers spent: {:02x?} {:02x?}",
+                &m.sell[..4],
+                &m.buy[..4]
+            );
+        }
+    }
+
+    let unmatched: Vec<&str> = book
+        .iter()
+        .filter(|o| !spent.contains(&o.nullifier_hash))
+        .map(|o| o.owner.as_str())
+        .collect();
+    println!(
+        "  Unmatched (no counterparty whose quantity fits both limits): {}",
+        unmatched.join(", ")
+    );
+
+    // Step 4: Matching again cannot reuse a spent nullifier
+    println!("\n🛡️  Step 4: Double-Match Prevention");
+
+    let rematched = match_orders(&book, wsteth, block_timestamp, &spent);
+    println!("  Matches on second pass: {}", rematched.len());
+    println!("  ✅ Each nullifier was matched at most once");
+    #[cfg(feature = "sqlite")]
+    if let Some(store) = &store {
+        println!(
+            "  Active orders in DEMO_ORDER_DB: {}",
+            store.active_orders()?.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Builds Alice's order from the CLI arguments, commits it in a one-leaf tree and assembles the
+/// guest inputs.
+fn order_inputs(args: &OrderArgs) -> Result<SP1Stdin, Box<dyn Error>> {
+    let block_timestamp = match args.block_timestamp {
+        Some(timestamp) => timestamp,
+        None => demo_block_timestamp()?,
+    };
+    let deadline = match args.deadline {
+        Some(deadline) => deadline,
+        None => demo_deadline(block_timestamp)?,
+    };
 
-    // Create test data
     let alice_secret = [1u8; 32];
     let alice_order = OrderData {
+        domain: DEMO_DOMAIN,
         wallet_address: [1u8; 20],
         token_in: [0xAu8; 20],
         token_out: [0xBu8; 20],
-        amount_in: 5000000000000000000u64,
-        min_amount_out: 10000000000u64,
-        target_price: 2000000000u64,
-        deadline: 1735689600u64,
+        amount_in: args.amount_in,
+        min_amount_out: args.min_amount_out,
+        target_price: args.target_price,
+        deadline,
+        kind: order_kind(args.exact_amount_out, args.max_amount_in)?,
+        max_price_impact_bps: args.max_price_impact_bps,
+        fee_bps: args.fee_bps,
+        group_id: args
+            .group_id
+            .as_deref()
+            .map(|hex_str| hex32(hex_str, "--group-id"))
+            .transpose()?,
     };
 
     let market_conditions = MarketConditions {
-        current_price: 2050000000u64,
-        block_timestamp: 1735600000u64,
+        current_price: args.current_price,
+        block_timestamp,
+        reference_price: args.reference_price,
     };
 
-    let alice_balance = 10000000000000000000u64;
+    let alice_balance = args.balance;
     let order_context = hash_order(&alice_order);
 
     let (alice_commitment, alice_nullifier) =
         create_order_commitment(&alice_order, &alice_secret, alice_balance, &order_context);
 
+    let blinding = match &args.blinding {
+        Some(hex_str) => Some(hex32(hex_str, "--blinding")?),
+        None => None,
+    };
+    let metadata_hash = match &args.metadata_hash {
+        Some(hex_str) => hex32(hex_str, "--metadata-hash")?,
+        None => [0u8; 32],
+    };
+    let balance_blinding = match &args.balance_blinding {
+        Some(hex_str) => Some(hex32(hex_str, "--balance-blinding")?),
+        None => None,
+    };
+    let commitment_hash = match &balance_blinding {
+        Some(balance_blinding) => compute_balance_hiding_commitment(
+            &alice_order,
+            &alice_commitment.nullifier,
+            alice_balance,
+            balance_blinding,
+        ),
+        None => alice_nullifier.commitment_hash,
+    };
+    let bound_commitment = bind_metadata(&commitment_hash, &metadata_hash);
+    let leaf = match &blinding {
+        Some(blinding) => blind_commitment(&bound_commitment, blinding),
+        None => bound_commitment,
+    };
+
     // Build tree with Alice's commitment
-    let mut tree = CommitmentMerkleTree::new();
-    tree.add_commitment(alice_nullifier.commitment_hash, "Alice".to_string());
+    let mut tree = CommitmentMerkleTree::<String>::new();
+    tree.add_commitment(leaf, "Alice".to_string())?;
 
-    let (tree_root, _) = tree.build_tree();
-    let (siblings, indices) = tree.generate_proof(alice_nullifier.commitment_hash)?;
+    let (tree_root, _) = tree.build_tree()?;
+    let (siblings, indices) = tree.generate_proof(leaf)?.into_parts();
 
-    println!("  Order: 5 ETH → USDC at $2000 target");
-    println!("  Market: $2050 (favorable)");
-    println!("  Balance: 10 ETH (sufficient)");
+    println!(
+        "  Order: {} in → min {} out at {} target",
+        args.amount_in, args.min_amount_out, args.target_price
+    );
+    println!("  Market: {} at {}", args.current_price, block_timestamp);
+    println!("  Balance: {}", alice_balance);
+
+    let nullifier_hash = args.nullifier_bits.truncate(alice_nullifier.nullifier_hash);
+
+    // Spent-nullifier set the guest checks the nullifier against, if any
+    let spent_proof = if args.replay || !args.spent_nullifiers.is_empty() {
+        let mut spent = args
+            .spent_nullifiers
+            .iter()
+            .map(|hex_str| hex32(hex_str, "--spent-nullifier"))
+            .collect::<Result<Vec<_>, _>>()?;
+        if args.replay {
+            spent.push(nullifier_hash);
+        }
+        println!(
+            "  Spent nullifiers: {} (replay: {})",
+            spent.len(),
+            args.replay
+        );
+        let spent_tree = spent_nullifier_tree(&spent)?;
+        Some(prove_not_spent(&spent_tree, &nullifier_hash))
+    } else {
+        None
+    };
 
     // Setup SP1 inputs
-    let mut stdin = SP1Stdin::new();
+    let inputs = OrderProofInputs {
+        market: market_conditions,
+        merkle_root: tree_root,
+        nullifier_hash,
+        domain: DEMO_DOMAIN,
+        disclosure: args.disclosure,
+        nullifier_bits: args.nullifier_bits,
+        fee_recipient: DEMO_FEE_RECIPIENT,
+        cross_check: args.cross_check,
+        order: alice_order,
+        nullifier: alice_commitment.nullifier,
+        balance: alice_balance,
+        siblings,
+        indices,
+        blinding,
+        metadata_hash,
+        balance_proof: None,
+        spent_proof,
+        balance_blinding,
+        price_proof: None,
+    };
 
-    // Public inputs
-    stdin.write(&market_conditions);
-    stdin.write(&tree_root);
-    stdin.write(&alice_nullifier.nullifier_hash);
+    Ok(build_stdin(&inputs)?)
+}
 
-    // Private inputs
-    stdin.write(&alice_order);
-    stdin.write(&alice_commitment.nullifier);
-    stdin.write(&alice_balance);
-    stdin.write(&siblings);
-    stdin.write(&indices);
+/// Exact-out when `--exact-amount-out` is given, exact-in otherwise
+fn order_kind(
+    exact_amount_out: Option<u64>,
+    max_amount_in: Option<u64>,
+) -> Result<OrderKind, Box<dyn Error>> {
+    Ok(match (exact_amount_out, max_amount_in) {
+        (Some(exact_amount_out), Some(max_amount_in)) => OrderKind::ExactOut {
+            exact_amount_out,
+            max_amount_in,
+        },
+        (None, None) => OrderKind::ExactIn,
+        _ => return Err("--exact-amount-out and --max-amount-in must be given together".into()),
+    })
+}
+
+fn run_commit(args: &CommitArgs) -> Result<(), Box<dyn Error>> {
+    let kind = order_kind(args.exact_amount_out, args.max_amount_in)?;
+    let order = OrderData {
+        domain: OrderDomain {
+            chain_id: args.chain_id.unwrap_or(DEMO_DOMAIN.chain_id),
+            pool_address: match &args.pool_address {
+                Some(hex_str) => hex20(hex_str, "--pool-address")?,
+                None => DEMO_DOMAIN.pool_address,
+            },
+            amount_repr: args.amount_repr,
+        },
+        wallet_address: hex20(&args.wallet, "--wallet")?,
+        token_in: hex20(&args.token_in, "--token-in")?,
+        token_out: hex20(&args.token_out, "--token-out")?,
+        amount_in: args.amount_in,
+        min_amount_out: args.min_amount_out,
+        target_price: args.target_price,
+        deadline: args.deadline,
+        kind,
+        max_price_impact_bps: args.max_price_impact_bps,
+        fee_bps: args.fee_bps,
+        group_id: args
+            .group_id
+            .as_deref()
+            .map(|hex_str| hex32(hex_str, "--group-id"))
+            .transpose()?,
+    };
+    let secret = hex32(&args.secret, "--secret")?;
+    let order_hash = hash_order(&order);
+    let context = match &args.context {
+        Some(hex_str) => hex32(hex_str, "--context")?,
+        None => order_hash,
+    };
+
+    let (commitment, nullifier_data) =
+        create_order_commitment(&order, &secret, args.balance, &context);
+    let to_hex = |bytes: &[u8; 32]| format!("0x{}", hex::encode(bytes));
+    let output = CommitOutput {
+        order_hash: to_hex(&order_hash),
+        nullifier: to_hex(&commitment.nullifier),
+        nullifier_hash: to_hex(&nullifier_data.nullifier_hash),
+        commitment_hash: to_hex(&nullifier_data.commitment_hash),
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// Decodes a 0x-optional hex string into 20 bytes
+fn hex20(hex_str: &str, flag: &str) -> Result<[u8; 20], Box<dyn Error>> {
+    <[u8; 20]>::try_from(hex::decode(hex_str.trim_start_matches("0x"))?)
+        .map_err(|_| format!("{flag} must be 20 bytes").into())
+}
+
+/// Decodes a 0x-optional hex string into 32 bytes
+fn hex32(hex_str: &str, flag: &str) -> Result<[u8; 32], Box<dyn Error>> {
+    <[u8; 32]>::try_from(hex::decode(hex_str.trim_start_matches("0x"))?)
+        .map_err(|_| format!("{flag} must be 32 bytes").into())
+}
+
+fn run_sp1_nullifier_test(args: &OrderArgs) -> Result<(), Box<dyn Error>> {
+    println!("\n🔬 SP1 NULLIFIER TEST");
+    println!("═══════════════════════");
+
+    let client = ProverClient::from_env();
+    let stdin = order_inputs(args)?;
 
     println!("  🔄 Executing SP1 program...");
     let (mut output, report) = client.execute(FIBONACCI_ELF, &stdin).run()?;
 
     // Read outputs
-    let is_valid = output.read::<bool>();
-    let nullifier_hash = output.read::<[u8; 32]>();
-    let wallet_address = output.read::<[u8; 20]>();
-    let amount_in = output.read::<u64>();
-    let min_amount_out = output.read::<u64>();
+    let outputs = output.read::<GuestOutputs>();
 
     println!("  ✅ SP1 Results:");
-    println!("    Valid: {}", is_valid);
-    println!("    Nullifier: {:02x?}", &nullifier_hash[..8]);
-    println!("    Wallet: {:02x?}", &wallet_address[..4]);
-    println!("    Amount in: {}", amount_in);
-    println!("    Min out: {}", min_amount_out);
+    println!("    Valid: {}", outputs.valid);
+    println!("    Nullifier: {:02x?}", &outputs.nullifier_hash[..8]);
+    println!("    Metadata bound: {}", outputs.metadata_present);
+    println!(
+        "    Domain: chain {} pool {:02x?}",
+        outputs.domain.chain_id,
+        &outputs.domain.pool_address[..4]
+    );
+    println!("    Market timestamp: {}", outputs.block_timestamp);
+    if let Some(spent_root) = &outputs.spent_root {
+        println!("    Checked against spent set: {:02x?}", &spent_root[..8]);
+    }
+    if let Some(cross_check_ok) = outputs.cross_check_ok {
+        println!("    Reference cross-check agrees: {}", cross_check_ok);
+    }
+
+    if let Some(settlement) = &outputs.settlement {
+        println!("    Wallet: {:02x?}", &settlement.wallet_address[..4]);
+        println!("    Amount in: {}", settlement.amount_in);
+        println!("    Min out: {}", settlement.min_amount_out);
+        println!(
+            "    Fee: {} to {:02x?}",
+            settlement.fee_amount,
+            &settlement.fee_recipient[..4]
+        );
+    } else {
+        println!("    Settlement fields: hidden ({:?})", args.disclosure);
+    }
     println!("    Cycles: {}", report.total_instruction_count());
+    let mut regions: Vec<_> = report.cycle_tracker.iter().collect();
+    regions.sort();
+    for (region, cycles) in regions {
+        println!("      {region}: {cycles}");
+    }
 
-    if is_valid {
+    if outputs.valid {
         println!("  🎯 Order ready for execution!");
     } else {
         println!("  ❌ Order validation failed!");
@@ -481,101 +1105,185 @@ fn run_sp1_nullifier_test() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn run_sp1_nullifier_prove() -> Result<(), Box<dyn Error>> {
+/// Executes the guest twice on the same stdin and exits with status 1 if the committed public
+/// values differ, catching nondeterminism (e.g. hash map iteration order) reaching the outputs.
+/// Proofs of the same inputs may differ; their public values must not.
+fn check_determinism(args: &OrderArgs) -> Result<(), Box<dyn Error>> {
+    println!("\n🔁 DETERMINISM CHECK");
+    println!("═══════════════════════");
+
+    let client = ProverClient::from_env();
+    let stdin = order_inputs(args)?;
+
+    let (mut first, _) = client.execute(FIBONACCI_ELF, &stdin).run()?;
+    let (mut second, _) = client.execute(FIBONACCI_ELF, &stdin).run()?;
+
+    if first.as_slice() != second.as_slice() {
+        eprintln!("  ❌ Public values differ between runs:");
+        eprintln!("    first:  {:?}", first.read::<GuestOutputs>());
+        eprintln!("    second: {:?}", second.read::<GuestOutputs>());
+        std::process::exit(1);
+    }
+
+    let outputs = first.read::<GuestOutputs>();
+    println!(
+        "  ✅ Both runs committed the same {} bytes",
+        first.as_slice().len()
+    );
+    println!("    Valid: {}", outputs.valid);
+    println!("    Nullifier: {:02x?}", &outputs.nullifier_hash[..8]);
+    Ok(())
+}
+
+fn run_sp1_nullifier_prove(args: &OrderArgs) -> Result<(), Box<dyn Error>> {
     println!("\n🔬 SP1 NULLIFIER PROVE");
     println!("═══════════════════════");
 
     let client = ProverClient::from_env();
+    let (pk, _) = client.setup(FIBONACCI_ELF);
+    let stdin = order_inputs(args)?;
 
-    let (pk, vk) = client.setup(FIBONACCI_ELF);
+    let proof = client.prove(&pk, &stdin).groth16().run()?;
 
+    println!("    Proof: {:?}", proof);
+    Ok(())
+}
 
+fn run_bench(args: &OrderArgs, iterations: u32) -> Result<(), Box<dyn Error>> {
+    println!("\n⏱️  SP1 EXECUTION BENCH");
+    println!("═══════════════════════");
 
-    // Create test data
-    let alice_secret = [1u8; 32];
-    let alice_order = OrderData {
-        wallet_address: [1u8; 20],
-        token_in: [0xAu8; 20],
-        token_out: [0xBu8; 20],
-        amount_in: 5000000000000000000u64,
-        min_amount_out: 10000000000u64,
-        target_price: 2000000000u64,
-        deadline: 1735689600u64,
-    };
+    let client = ProverClient::from_env();
+    let stdin = order_inputs(args)?;
 
-    let market_conditions = MarketConditions {
-        current_price: 2050000000u64,
-        block_timestamp: 1735600000u64,
-    };
+    let mut cycles = 0;
+    let started = Instant::now();
+    for _ in 0..iterations {
+        let (_, report) = client.execute(FIBONACCI_ELF, &stdin).run()?;
+        cycles = report.total_instruction_count();
+    }
+    let elapsed = started.elapsed();
 
-    let alice_balance = 10000000000000000000u64;
-    let order_context = hash_order(&alice_order);
+    println!("  Iterations: {}", iterations);
+    println!("  Cycles per execution: {}", cycles);
+    println!("  Average wall time: {:?}", elapsed / iterations.max(1));
 
-    let (alice_commitment, alice_nullifier) =
-        create_order_commitment(&alice_order, &alice_secret, alice_balance, &order_context);
+    Ok(())
+}
 
-    // Build tree with Alice's commitment
-    let mut tree = CommitmentMerkleTree::new();
-    tree.add_commitment(alice_nullifier.commitment_hash, "Alice".to_string());
+fn run_depth_bench(max_depth: usize) -> Result<(), Box<dyn Error>> {
+    println!("\n🌳 MERKLE DEPTH BENCH");
+    println!("═══════════════════════");
 
-    let (tree_root, _) = tree.build_tree();
-    let (siblings, indices) = tree.generate_proof(alice_nullifier.commitment_hash)?;
+    let client = ProverClient::from_env();
+    let results = merkle_depth_cycles(&client, FIBONACCI_ELF, max_depth)?;
+
+    let base_cycles = results.first().map(|(_, cycles)| *cycles).unwrap_or(0);
+    println!("  {:>5}  {:>12}  {:>12}", "depth", "cycles", "vs depth 1");
+    for (depth, cycles) in &results {
+        println!(
+            "  {:>5}  {:>12}  {:>12}",
+            depth,
+            cycles,
+            cycles.saturating_sub(base_cycles)
+        );
+    }
 
-    println!("  Order: 5 ETH → USDC at $2000 target");
-    println!("  Market: $2050 (favorable)");
-    println!("  Balance: 10 ETH (sufficient)");
+    Ok(())
+}
 
-    // Setup SP1 inputs
-    let mut stdin = SP1Stdin::new();
+fn export_calldata(
+    args: &OrderArgs,
+    system: ProofSystem,
+    output: &str,
+) -> Result<(), Box<dyn Error>> {
+    println!("\n📤 EXPORT CALLDATA");
+    println!("═══════════════════════");
+
+    let client = ProverClient::from_env();
+    let (pk, vk) = client.setup(FIBONACCI_ELF);
+    let stdin = order_inputs(args)?;
 
-    // Public inputs
-    stdin.write(&market_conditions);
-    stdin.write(&tree_root);
-    stdin.write(&alice_nullifier.nullifier_hash);
+    let proof = match system {
+        ProofSystem::Plonk => client.prove(&pk, &stdin).plonk().run()?,
+        ProofSystem::Groth16 => client.prove(&pk, &stdin).groth16().run()?,
+    };
 
-    // Private inputs
-    stdin.write(&alice_order);
-    stdin.write(&alice_commitment.nullifier);
-    stdin.write(&alice_balance);
-    stdin.write(&siblings);
-    stdin.write(&indices);
+    let calldata = Calldata {
+        vkey: vk.bytes32(),
+        public_values: format!("0x{}", hex::encode(proof.public_values.as_slice())),
+        proof: format!("0x{}", hex::encode(proof.bytes())),
+    };
+    let output = output_path(output, &calldata.vkey)?;
+    std::fs::write(&output, serde_json::to_string_pretty(&calldata)?)?;
 
-    let mut proof = client.prove(&pk, &stdin).groth16().run()?;
+    println!("  Proof system: {:?}", system);
+    println!("  Verification key: {}", calldata.vkey);
+    println!("  ✅ Calldata written to {}", output.display());
 
-    println!("    Proof: {:?}", proof);
     Ok(())
 }
 
+/// Prints the vkey hash of the compiled guest and exits with status 1 if it is not `expect`,
+/// so a release gate catches unintended program changes.
+fn check_vkey(expect: Option<&str>) {
+    let client = ProverClient::from_env();
+    let (_, vk) = client.setup(FIBONACCI_ELF);
+    let vkey_hash = format!("0x{}", hex::encode(vk.hash_bytes()));
+    println!("{}", vkey_hash);
+
+    if let Some(expected) = expect {
+        let expected = expected.trim_start_matches("0x").to_lowercase();
+        if expected != vkey_hash[2..] {
+            eprintln!("vkey changed: expected 0x{}, got {}", expected, vkey_hash);
+            std::process::exit(1);
+        }
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     sp1_sdk::utils::setup_logger();
     dotenv::dotenv().ok();
 
-    let args = Args::parse();
+    let cli = Cli::parse();
 
-    if !args.execute && !args.prove {
-        eprintln!("Error: You must specify either --execute or --prove");
-        std::process::exit(1);
+    // `commit` prints bare JSON for other tools to parse
+    if !matches!(cli.command, Command::Commit(_)) {
+        println!("🌊 Nullifier-based Dark Pool");
     }
 
-    println!("🌊 Nullifier-based Dark Pool");
-    println!("Demo: {}", args.demo);
-    println!("Mode: {}", if args.execute { "Execute" } else { "Prove" });
-
-    match args.demo.as_str() {
-        "nullifier-flow" => {
-            demonstrate_nullifier_flow()?;
-            if args.execute {
-                run_sp1_nullifier_test()?;
-            }
-
-            if args.prove {
-                run_sp1_nullifier_prove()?;
+    match &cli.command {
+        Command::Demo {
+            name,
+            export_fixtures,
+        } => match name.as_str() {
+            "nullifier-flow" => demonstrate_nullifier_flow(export_fixtures.as_deref())?,
+            "matching" => demonstrate_matching()?,
+            _ => {
+                eprintln!("Unknown demo: {}", name);
+                std::process::exit(1);
             }
-        }
-        _ => {
-            eprintln!("Unknown demo: {}", args.demo);
-            std::process::exit(1);
-        }
+        },
+        Command::Execute {
+            order,
+            check_determinism: true,
+        } => check_determinism(order)?,
+        Command::Execute { order, .. } => run_sp1_nullifier_test(order)?,
+        Command::Prove(order) => run_sp1_nullifier_prove(order)?,
+        Command::Bench {
+            bench_depth: Some(max_depth),
+            ..
+        } => run_depth_bench(*max_depth)?,
+        Command::Bench {
+            order, iterations, ..
+        } => run_bench(order, *iterations)?,
+        Command::ExportCalldata {
+            order,
+            system,
+            output,
+        } => export_calldata(order, *system, output)?,
+        Command::Vkey { expect } => check_vkey(expect.as_deref()),
+        Command::Commit(args) => run_commit(args)?,
     }
 
     Ok(())
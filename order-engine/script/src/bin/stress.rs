@@ -0,0 +1,190 @@
+//! Concurrent load against a running server
+//!
+//! Fires `STRESS_REQUESTS` (default 16) simultaneous `/rpc` execute calls, each for a distinct
+//! order, and checks every response carries that order's own nullifier hash and validity, so
+//! shared prover state or caching leaking between requests shows up as a mismatch. Every odd
+//! order is priced below its target and must come back invalid.
+//!
+//! Usage:
+//! ```shell
+//! cargo run --release -p server &
+//! STRESS_REQUESTS=32 SERVER_URL=http://localhost:8080 cargo run --release --bin stress
+//! ```
+
+use fibonacci_lib::{
+    create_order_commitment, hash_order, CommitmentMerkleTree, OrderData, OrderKind,
+};
+use fibonacci_script::DEMO_DOMAIN;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::env;
+use std::error::Error;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CURRENT_PRICE: u64 = 2050000000;
+
+/// One stress order: its `/rpc` execute call and the outputs the guest must report for it
+struct Case {
+    call: Value,
+    nullifier_hash: String,
+    valid: bool,
+}
+
+fn hex0x(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Order `i`, unique per `i` in secret, wallet and amount; odd orders target a price above the
+/// market so they cannot execute
+fn stress_case(i: u32, now: u64) -> Result<Case, Box<dyn Error>> {
+    let mut secret = [0u8; 32];
+    secret[..4].copy_from_slice(&(i + 1).to_le_bytes());
+    let mut wallet = [0x1u8; 20];
+    wallet[..4].copy_from_slice(&(i + 1).to_le_bytes());
+    let valid = i.is_multiple_of(2);
+
+    let order = OrderData {
+        domain: DEMO_DOMAIN,
+        wallet_address: wallet,
+        token_in: [0xAu8; 20],
+        token_out: [0xBu8; 20],
+        amount_in: 1000000000000000000 + u64::from(i),
+        min_amount_out: 1000000000,
+        target_price: if valid { 2000000000 } else { 2100000000 },
+        deadline: now + 3600,
+        kind: OrderKind::ExactIn,
+        max_price_impact_bps: None,
+        fee_bps: 0,
+        group_id: None,
+    };
+    let balance = 10000000000000000000u64;
+    let (commitment, nullifier) =
+        create_order_commitment(&order, &secret, balance, &hash_order(&order));
+
+    let mut tree = CommitmentMerkleTree::<()>::new();
+    tree.add_commitment(nullifier.commitment_hash, ())?;
+    let (root, _) = tree.build_tree()?;
+    let (siblings, indices) = tree.generate_proof(nullifier.commitment_hash)?.into_parts();
+
+    let call = json!({
+        "method": "execute",
+        "params": {
+            "market": { "current_price": CURRENT_PRICE, "block_timestamp": now },
+            "tree_root": hex0x(&root),
+            "nullifier_hash": hex0x(&nullifier.nullifier_hash),
+            "domain": {
+                "chain_id": DEMO_DOMAIN.chain_id,
+                "pool_address": hex0x(&DEMO_DOMAIN.pool_address),
+            },
+            "order": {
+                "wallet_address": hex0x(&order.wallet_address),
+                "token_in": hex0x(&order.token_in),
+                "token_out": hex0x(&order.token_out),
+                "amount_in": order.amount_in,
+                "min_amount_out": order.min_amount_out,
+                "target_price": order.target_price,
+                "deadline": order.deadline,
+            },
+            "commitment_nullifier": hex0x(&commitment.nullifier),
+            "balance": balance,
+            "siblings": siblings.iter().map(|s| hex0x(s)).collect::<Vec<_>>(),
+            "indices": indices,
+        }
+    });
+
+    Ok(Case {
+        call,
+        nullifier_hash: hex0x(&nullifier.nullifier_hash),
+        valid,
+    })
+}
+
+/// Sends `case` as a one-call batch and describes any way the result differs from it
+fn check_case(client: &reqwest::blocking::Client, url: &str, case: &Case) -> Result<(), String> {
+    let responses: Vec<Value> = client
+        .post(url)
+        .json(&[&case.call])
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.json())
+        .map_err(|e| format!("request failed: {}", e))?;
+
+    let result = match responses.first() {
+        Some(Value::Object(entry)) if entry.contains_key("result") => &entry["result"],
+        other => return Err(format!("call failed: {:?}", other)),
+    };
+
+    if result["nullifier_hash"] != case.nullifier_hash.as_str() {
+        return Err(format!(
+            "got nullifier hash {} instead of {}",
+            result["nullifier_hash"], case.nullifier_hash
+        ));
+    }
+    if result["valid"] != case.valid {
+        return Err(format!(
+            "got valid {} instead of {}",
+            result["valid"], case.valid
+        ));
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    dotenv::dotenv().ok();
+
+    let requests: u32 = env::var("STRESS_REQUESTS")
+        .map(|value| value.parse())
+        .unwrap_or(Ok(16))?;
+    let server = env::var("SERVER_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
+    let url = format!("{}/rpc", server.trim_end_matches('/'));
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    let cases = (0..requests)
+        .map(|i| stress_case(i, now))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Every order must be told apart by its nullifier hash
+    let distinct: HashSet<_> = cases.iter().map(|case| &case.nullifier_hash).collect();
+    assert_eq!(
+        distinct.len(),
+        cases.len(),
+        "stress orders share a nullifier hash"
+    );
+
+    println!(
+        "🔥 Sending {} concurrent execute calls to {}",
+        requests, url
+    );
+    let client = reqwest::blocking::Client::new();
+    let failures: Vec<(usize, String)> = thread::scope(|scope| {
+        let handles: Vec<_> = cases
+            .iter()
+            .map(|case| scope.spawn(|| check_case(&client, &url, case)))
+            .collect();
+        handles
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, handle)| match handle.join() {
+                Ok(Ok(())) => None,
+                Ok(Err(reason)) => Some((i, reason)),
+                Err(_) => Some((i, "request thread panicked".to_string())),
+            })
+            .collect()
+    });
+
+    for (i, reason) in &failures {
+        println!("  ❌ order {}: {}", i, reason);
+    }
+    if !failures.is_empty() {
+        return Err(format!(
+            "{} of {} calls returned the wrong outputs",
+            failures.len(),
+            requests
+        )
+        .into());
+    }
+
+    println!("  ✅ All {} responses matched their own order", requests);
+    Ok(())
+}
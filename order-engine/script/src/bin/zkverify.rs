@@ -3,21 +3,39 @@
 //! This script generates compressed SP1 proofs compatible with zkVerify's SP1 verification pallet.
 //! It follows the zkVerify documentation for SP1 proof submission.
 //!
+//! `--proof-mode plonk` generates a BN254 PLONK proof instead, encoded for SP1's PLONK verifier
+//! (the `SP1VerifierPlonk` contract and `sp1-verifier`) rather than for the SP1 pallet.
+//!
 //! Usage:
 //! ```shell
 //! RUST_LOG=info cargo run --release --bin zkverify -- --generate-proof
+//! RUST_LOG=info cargo run --release --bin zkverify -- --generate-proof --proof-mode plonk
+//! RUST_LOG=info cargo run --release --bin zkverify -- --generate-proof --output-file 'proofs/proof_{vkey}_{timestamp}.json'
+//! cargo run --release --bin zkverify -- --verify-file proof_zkverify.json
+//! cargo run --release --bin zkverify -- --submit --rpc-url <relayer url>
 //! ```
 
 use clap::Parser;
 use fibonacci_lib::{
-    create_order_commitment, hash_order, MarketConditions, OrderData,
+    build_stdin, create_order_commitment, hash_order, CommitmentMerkleTree, DisclosureLevel,
+    GuestOutputs, MarketConditions, NullifierBits, OrderData, OrderKind, OrderProofInputs,
+};
+use fibonacci_script::{
+    demo_block_timestamp, demo_deadline, output_path, DEMO_DOMAIN, DEMO_FEE_RECIPIENT,
 };
 use serde::{Deserialize, Serialize};
-use sp1_sdk::{include_elf, ProverClient, SP1Stdin, HashableKey};
+use sp1_sdk::{
+    include_elf, HashableKey, ProverClient, SP1Proof, SP1ProofWithPublicValues, SP1PublicValues,
+    SP1VerifyingKey, SP1_CIRCUIT_VERSION,
+};
 use std::env;
 use std::error::Error;
+use std::fmt;
 use std::fs::File;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
 /// The ELF file for our SP1 zkVM program
 pub const FIBONACCI_ELF: &[u8] = include_elf!("fibonacci-program");
@@ -31,19 +49,126 @@ struct Args {
     #[arg(long)]
     verify_locally: bool,
 
+    /// Proof file --generate-proof writes, and --verify-locally and --submit read. `{vkey}` and
+    /// `{timestamp}` are filled in when generating; missing directories are created.
     #[arg(long, default_value = "proof_zkverify.json")]
     output_file: String,
+
+    /// Verify a saved proof file against the current program, printing PASS or FAIL
+    #[arg(long, value_name = "PATH")]
+    verify_file: Option<String>,
+
+    /// Proof system used by --generate-proof
+    #[arg(long, value_enum, default_value_t = ProofMode::Compressed)]
+    proof_mode: ProofMode,
+
+    /// Submit the proof file to zkVerify and wait for its receipt
+    #[arg(long, requires = "rpc_url")]
+    submit: bool,
+
+    /// Base URL of the zkVerify relayer API used by --submit
+    #[arg(long)]
+    rpc_url: Option<String>,
+
+    /// Relayer API key, appended to each request path when set
+    #[arg(long, env = "ZKVERIFY_API_KEY")]
+    api_key: Option<String>,
+
+    /// Where --submit writes the verification receipt
+    #[arg(long, default_value = "zkverify_receipt.json")]
+    receipt_file: String,
+}
+
+/// Time between job status requests while waiting for a receipt
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Status requests made before giving up on a submitted proof
+const MAX_POLLS: u32 = 120;
+
+/// SP1 proof system a proof file is generated with
+#[derive(clap::ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum ProofMode {
+    /// STARK proof for zkVerify's SP1 pallet (`proofType: "sp1"`), the one --submit targets
+    #[default]
+    Compressed,
+    /// BN254 PLONK proof in the encoding SP1's on-chain PLONK verifier takes; the SP1 pallet
+    /// does not accept it, so it can only be submitted to a PLONK verifier
+    Plonk,
 }
 
 /// zkVerify-compatible SP1 proof output structure
 #[derive(Serialize, Deserialize, Debug)]
 struct ZkVerifyProofOutput {
-    /// The image ID (verification key hash) for zkVerify
+    /// The image ID: the vkey `hash_bytes` for compressed proofs, the BN254 program vkey
+    /// (`bytes32`) for PLONK proofs
     image_id: String,
     /// Public inputs/values as hex string  
     pub_inputs: String,
-    /// The compressed SP1 proof as hex string
+    /// The proof as hex string: the bincode `SP1Proof` for compressed proofs, the on-chain
+    /// encoding (`SP1ProofWithPublicValues::bytes`) for PLONK proofs
     proof: String,
+    /// Files written before PLONK support are compressed proofs
+    #[serde(default)]
+    proof_mode: ProofMode,
+}
+
+/// Proof submission body of the zkVerify relayer API
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SubmitProofRequest<'a> {
+    proof_type: &'static str,
+    vk_registered: bool,
+    proof_data: SubmitProofData<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SubmitProofData<'a> {
+    proof: &'a str,
+    public_signals: &'a str,
+    vk: &'a str,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubmitProofResponse {
+    job_id: String,
+    /// `"failed"` when the relayer already rejected the proof on submission
+    optimistic_verify: Option<String>,
+}
+
+/// Why a zkVerify submission did not produce a receipt
+#[derive(Debug)]
+enum SubmitError {
+    /// The relayer could not be reached, or answered with an HTTP error or an unexpected body
+    Network(reqwest::Error),
+    /// zkVerify rejected the proof as invalid
+    Rejected { job_id: String, reason: String },
+    /// The proof was accepted but not finalized within `MAX_POLLS` status requests
+    Timeout { job_id: String },
+}
+
+impl fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubmitError::Network(e) => write!(f, "zkVerify request failed: {}", e),
+            SubmitError::Rejected { job_id, reason } => {
+                write!(f, "zkVerify rejected the proof (job {}): {}", job_id, reason)
+            }
+            SubmitError::Timeout { job_id } => {
+                write!(f, "zkVerify job {} was not finalized in time", job_id)
+            }
+        }
+    }
+}
+
+impl Error for SubmitError {}
+
+impl From<reqwest::Error> for SubmitError {
+    fn from(e: reqwest::Error) -> Self {
+        SubmitError::Network(e)
+    }
 }
 
 /// Helper function to convert bytes to hex with 0x prefix
@@ -51,8 +176,18 @@ fn to_hex_with_prefix(bytes: &[u8]) -> String {
     format!("0x{}", hex::encode(bytes))
 }
 
+/// Image id a proof generated in `mode` carries for the program `vk` belongs to
+fn image_id(vk: &SP1VerifyingKey, mode: ProofMode) -> String {
+    match mode {
+        ProofMode::Compressed => to_hex_with_prefix(&vk.hash_bytes()),
+        ProofMode::Plonk => vk.bytes32(),
+    }
+}
+
 /// Generate zkVerify-compatible SP1 proof
-fn generate_zkverify_proof() -> Result<(), Box<dyn Error>> {
+/// Generates the proof file at `output_file` (a template, see `output_path`) and returns the
+/// path it was written to
+fn generate_zkverify_proof(mode: ProofMode, output_file: &str) -> Result<PathBuf, Box<dyn Error>> {
     println!("🔬 SP1 + zkVerify Integration");
     println!("══════════════════════════");
 
@@ -66,20 +201,27 @@ fn generate_zkverify_proof() -> Result<(), Box<dyn Error>> {
     println!("  📋 Program VK: {:?}", vk.hash_bytes());
 
     // Create test order data
+    let block_timestamp = demo_block_timestamp()?;
     let alice_secret = [1u8; 32];
     let alice_order = OrderData {
+        domain: DEMO_DOMAIN,
         wallet_address: [1u8; 20],
         token_in: [0xAu8; 20],
         token_out: [0xBu8; 20],
         amount_in: 5000000000000000000u64,
         min_amount_out: 10000000000u64,
         target_price: 2000000000u64,
-        deadline: 1735689600u64,
+        deadline: demo_deadline(block_timestamp)?,
+        kind: OrderKind::ExactIn,
+        max_price_impact_bps: None,
+        fee_bps: 0,
+        group_id: None,
     };
 
     let market_conditions = MarketConditions {
         current_price: 2050000000u64,
-        block_timestamp: 1735600000u64,
+        block_timestamp,
+        reference_price: 0,
     };
 
     let alice_balance = 10000000000000000000u64;
@@ -93,81 +235,124 @@ fn generate_zkverify_proof() -> Result<(), Box<dyn Error>> {
     println!("    Target Price: $2000");
     println!("    Market Price: $2050 ✅");
 
-    // Create minimal Merkle tree
-    let tree_root = alice_nullifier.commitment_hash; // Single-node tree
-    let siblings: Vec<[u8; 32]> = vec![]; // Empty proof for single node
-    let indices: Vec<u8> = vec![]; // Empty indices for single node
+    // Create minimal Merkle tree (a lone leaf is paired with itself)
+    let mut tree = CommitmentMerkleTree::<()>::new();
+    tree.add_commitment(alice_nullifier.commitment_hash, ())?;
+    let (tree_root, _) = tree.build_tree()?;
+    let (siblings, indices) = tree.generate_proof(alice_nullifier.commitment_hash)?.into_parts();
 
     // Setup SP1 inputs
-    let mut stdin = SP1Stdin::new();
-
-    // Public inputs
-    stdin.write(&market_conditions);
-    stdin.write(&tree_root);
-    stdin.write(&alice_nullifier.nullifier_hash);
-
-    // Private inputs
-    stdin.write(&alice_order);
-    stdin.write(&alice_commitment.nullifier);
-    stdin.write(&alice_balance);
-    stdin.write(&siblings);
-    stdin.write(&indices);
-
-    println!("  🔄 Generating shrink SP1 proof for zkVerify testnet...");
-
-    // Generate shrink proof for zkVerify (required format)
-    let shrink_proof = client
-        .prove(&pk, &stdin)
-        .shrink()
-        .run()?;
-
-    println!("  ✅ Shrink proof generated and ready for zkVerify!");
+    let stdin = build_stdin(&OrderProofInputs {
+        market: market_conditions,
+        merkle_root: tree_root,
+        nullifier_hash: alice_nullifier.nullifier_hash,
+        domain: DEMO_DOMAIN,
+        disclosure: DisclosureLevel::Full,
+        nullifier_bits: NullifierBits::Bits256,
+        fee_recipient: DEMO_FEE_RECIPIENT,
+        cross_check: false,
+        order: alice_order,
+        nullifier: alice_commitment.nullifier,
+        balance: alice_balance,
+        siblings,
+        indices,
+        blinding: None,
+        metadata_hash: [0u8; 32],
+        balance_proof: None,
+        spent_proof: None,
+        balance_blinding: None,
+        price_proof: None,
+    })?;
+
+    let (proof, proof_bytes) = match mode {
+        ProofMode::Compressed => {
+            println!("  🔄 Generating compressed SP1 proof for zkVerify testnet...");
+
+            // Generate compressed proof for zkVerify (required format)
+            let compressed_proof = client
+                .prove(&pk, &stdin)
+                .compressed()
+                .run()?;
+
+            println!("  ✅ Compressed proof generated and ready for zkVerify!");
+
+            // Serialize the compressed proof for zkVerify
+            let proof_bytes = bincode::serialize(&compressed_proof.proof)?;
+            (compressed_proof, proof_bytes)
+        }
+        ProofMode::Plonk => {
+            println!("  🔄 Generating PLONK SP1 proof...");
+
+            let plonk_proof = client
+                .prove(&pk, &stdin)
+                .plonk()
+                .run()?;
+
+            // The file only keeps the on-chain encoding, which --verify-locally cannot rebuild a
+            // proof from, so verify it now
+            client.verify(&plonk_proof, &vk)?;
+            println!("  ✅ PLONK proof generated and verified");
+
+            let proof_bytes = plonk_proof.bytes();
+            (plonk_proof, proof_bytes)
+        }
+    };
 
-    // Get verification key hash using SP1VerifyingKey::hash_bytes
-    let vk_hash: [u8; 32] = vk.hash_bytes();
-    
     // Get public values as bytes 
-    let public_values = shrink_proof.public_values.to_vec();
-
-    // Serialize the shrink proof for zkVerify
-    let proof_bytes = bincode::serialize(&shrink_proof.proof)?;
+    let public_values = proof.public_values.to_vec();
 
     println!("  📊 Proof details:");
-    println!("    Image ID: {}", to_hex_with_prefix(&vk_hash));
+    println!("    Image ID: {}", image_id(&vk, mode));
     println!("    Public values size: {} bytes", public_values.len());
     println!("    Proof size: {} bytes", proof_bytes.len());
 
     // Create zkVerify-compatible output
     let zkverify_output = ZkVerifyProofOutput {
-        image_id: to_hex_with_prefix(&vk_hash),
+        image_id: image_id(&vk, mode),
         pub_inputs: to_hex_with_prefix(&public_values),
         proof: to_hex_with_prefix(&proof_bytes),
+        proof_mode: mode,
     };
 
     println!("  💾 Saving zkVerify-compatible proof...");
 
     // Save to file
     let json_string = serde_json::to_string_pretty(&zkverify_output)?;
-    let mut file = File::create("proof_zkverify.json")?;
+    let output_file = output_path(output_file, &vk.bytes32())?;
+    let mut file = File::create(&output_file)?;
     file.write_all(json_string.as_bytes())?;
 
-    println!("  ✅ Proof saved to proof_zkverify.json");
+    println!("  ✅ Proof saved to {}", output_file.display());
     println!("\n🎯 Ready for zkVerify submission!");
     println!("  Next steps:");
-    println!("  1. Submit to zkVerify testnet using zkVerifyJS");
-    println!("  2. Get proof receipt from zkVerify");
-    println!("  3. Use receipt for on-chain verification");
+    println!("  1. Submit to zkVerify with --submit --rpc-url <url> (or zkVerifyJS)");
+    println!("  2. Use the saved receipt for on-chain verification");
 
+    Ok(output_file)
+}
+
+/// Errors if `proof` was generated for a different program than the one `vk` belongs to, e.g. a
+/// stale proof file left over from before a guest change
+fn check_image_id(proof: &ZkVerifyProofOutput, vk: &SP1VerifyingKey) -> Result<(), Box<dyn Error>> {
+    let expected_image_id = image_id(vk, proof.proof_mode);
+    if proof.image_id != expected_image_id {
+        return Err(format!(
+            "image id mismatch: proof file has {}, current program vkey hash is {} (regenerate with --generate-proof)",
+            proof.image_id, expected_image_id
+        )
+        .into());
+    }
     Ok(())
 }
 
-/// Verify proof locally before submitting to zkVerify
-fn verify_local_proof() -> Result<(), Box<dyn Error>> {
+/// Verify a saved proof file against the program compiled into this binary before submitting
+/// it, printing PASS or FAIL
+fn verify_local_proof(path: &Path) -> Result<(), Box<dyn Error>> {
     println!("🔍 Local Proof Verification");
     println!("══════════════════════════");
 
     // Load the generated proof
-    let proof_data = std::fs::read_to_string("proof_zkverify.json")?;
+    let proof_data = std::fs::read_to_string(path)?;
     let zkverify_proof: ZkVerifyProofOutput = serde_json::from_str(&proof_data)?;
 
     println!("  📋 Loaded proof:");
@@ -178,16 +363,168 @@ fn verify_local_proof() -> Result<(), Box<dyn Error>> {
     // Parse the public values to show what we're proving
     let pub_bytes = hex::decode(zkverify_proof.pub_inputs.trim_start_matches("0x"))?;
     
-    if pub_bytes.len() >= 33 { // At least bool (1) + nullifier (32)
-        let is_valid = pub_bytes[0] != 0;
-        let nullifier_hash = &pub_bytes[1..33];
-        
-        println!("  🔍 Proof validates:");
-        println!("    Order is valid: {}", is_valid);
-        println!("    Nullifier hash: {}...", hex::encode(&nullifier_hash[..8]));
+    match bincode::deserialize::<GuestOutputs>(&pub_bytes) {
+        Ok(outputs) => {
+            println!("  🔍 Public outputs:");
+            println!("    Order is valid: {}", outputs.valid);
+            println!("    Nullifier hash: {}", to_hex_with_prefix(&outputs.nullifier_hash));
+            println!("    Merkle root: {}", to_hex_with_prefix(&outputs.merkle_root));
+            println!("    Chain ID: {}", outputs.domain.chain_id);
+            println!("    Pool: {}", to_hex_with_prefix(&outputs.domain.pool_address));
+            println!("    Block timestamp: {}", outputs.block_timestamp);
+            println!("    Metadata present: {}", outputs.metadata_present);
+            if let Some(root) = outputs.balance_root {
+                println!("    Balance root: {}", to_hex_with_prefix(&root));
+            }
+            if let Some(root) = outputs.spent_root {
+                println!("    Spent root: {}", to_hex_with_prefix(&root));
+            }
+            if let Some(root) = outputs.price_root {
+                println!("    Price root: {}", to_hex_with_prefix(&root));
+            }
+            if let Some(settlement) = &outputs.settlement {
+                println!("    Wallet: {}", to_hex_with_prefix(&settlement.wallet_address));
+                println!("    Amount in: {}", settlement.amount_in);
+                println!("    Min amount out: {}", settlement.min_amount_out);
+                println!("    Fee amount: {}", settlement.fee_amount);
+                println!("    Fee recipient: {}", to_hex_with_prefix(&settlement.fee_recipient));
+            }
+        }
+        Err(e) => println!("  ⚠️  Public values are not GuestOutputs: {}", e),
+    }
+
+    match check_proof_file(&zkverify_proof, &pub_bytes) {
+        Ok(()) => {
+            println!("  ✅ PASS: proof is valid for the current program - ready for zkVerify!");
+            Ok(())
+        }
+        Err(e) => {
+            println!("  ❌ FAIL: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Checks `proof` was generated for the current program and, for compressed proofs, rebuilds and
+/// verifies it cryptographically
+fn check_proof_file(proof: &ZkVerifyProofOutput, pub_bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+    // Check the proof was generated for the program compiled into this binary
+    let client = ProverClient::from_env();
+    let (_, vk) = client.setup(FIBONACCI_ELF);
+    check_image_id(proof, &vk)?;
+    println!("  ✅ Image ID matches the current program");
+
+    if proof.proof_mode == ProofMode::Plonk {
+        println!("  ℹ️  PLONK proofs are verified when generated; the file only holds their on-chain encoding");
+        return Ok(());
+    }
+
+    // Rebuild the compressed proof and verify it cryptographically
+    let proof_bytes = hex::decode(proof.proof.trim_start_matches("0x"))?;
+    let proof = SP1ProofWithPublicValues {
+        proof: bincode::deserialize::<SP1Proof>(&proof_bytes)?,
+        public_values: SP1PublicValues::from(pub_bytes),
+        sp1_version: SP1_CIRCUIT_VERSION.to_string(),
+        tee_proof: None,
+    };
+    client.verify(&proof, &vk)?;
+
+    Ok(())
+}
+
+/// `{rpc_url}/{route}/{api_key}`, without the key segment when there is none
+fn relayer_url(rpc_url: &str, route: &str, api_key: Option<&str>) -> String {
+    let base = format!("{}/{}", rpc_url.trim_end_matches('/'), route);
+    match api_key {
+        Some(key) => format!("{}/{}", base, key),
+        None => base,
+    }
+}
+
+/// Submits `proof` to the zkVerify relayer and polls the job until it is finalized, returning
+/// the final job status as the receipt
+fn submit_to_zkverify(
+    proof: &ZkVerifyProofOutput,
+    rpc_url: &str,
+    api_key: Option<&str>,
+) -> Result<serde_json::Value, SubmitError> {
+    let client = reqwest::blocking::Client::new();
+
+    let submitted: SubmitProofResponse = client
+        .post(relayer_url(rpc_url, "submit-proof", api_key))
+        .json(&SubmitProofRequest {
+            proof_type: "sp1",
+            vk_registered: false,
+            proof_data: SubmitProofData {
+                proof: &proof.proof,
+                public_signals: &proof.pub_inputs,
+                vk: &proof.image_id,
+            },
+        })
+        .send()?
+        .error_for_status()?
+        .json()?;
+    let job_id = submitted.job_id;
+    println!("  📨 Submitted, job id {}", job_id);
+
+    if submitted.optimistic_verify.as_deref() == Some("failed") {
+        return Err(SubmitError::Rejected {
+            job_id,
+            reason: "optimistic verification failed".to_string(),
+        });
+    }
+
+    let status_url = format!(
+        "{}/{}",
+        relayer_url(rpc_url, "job-status", api_key),
+        job_id
+    );
+    for _ in 0..MAX_POLLS {
+        let status: serde_json::Value = client
+            .get(&status_url)
+            .send()?
+            .error_for_status()?
+            .json()?;
+
+        match status["status"].as_str() {
+            Some("Finalized") => return Ok(status),
+            Some("Failed") => {
+                let reason = status["statusDetails"]
+                    .as_str()
+                    .unwrap_or("verification failed")
+                    .to_string();
+                return Err(SubmitError::Rejected { job_id, reason });
+            }
+            other => println!("    status: {}", other.unwrap_or("unknown")),
+        }
+        thread::sleep(POLL_INTERVAL);
     }
 
-    println!("  ✅ Proof format verified - ready for zkVerify!");
+    Err(SubmitError::Timeout { job_id })
+}
+
+/// Submit the proof file at `proof_file` to zkVerify and save the verification receipt
+fn submit_proof(args: &Args, proof_file: &Path) -> Result<(), Box<dyn Error>> {
+    println!("📡 zkVerify Submission");
+    println!("══════════════════════════");
+
+    let proof_data = std::fs::read_to_string(proof_file)?;
+    let zkverify_proof: ZkVerifyProofOutput = serde_json::from_str(&proof_data)?;
+    if zkverify_proof.proof_mode != ProofMode::Compressed {
+        return Err("--submit sends proofs to zkVerify's SP1 pallet, which only accepts compressed proofs (--proof-mode compressed)".into());
+    }
+
+    // Do not spend a submission on a proof of an outdated program
+    let client = ProverClient::from_env();
+    let (_, vk) = client.setup(FIBONACCI_ELF);
+    check_image_id(&zkverify_proof, &vk)?;
+
+    let rpc_url = args.rpc_url.as_deref().ok_or("--submit requires --rpc-url")?;
+    let receipt = submit_to_zkverify(&zkverify_proof, rpc_url, args.api_key.as_deref())?;
+
+    let mut file = File::create(&args.receipt_file)?;
+    file.write_all(serde_json::to_string_pretty(&receipt)?.as_bytes())?;
+    println!("  ✅ Proof finalized, receipt saved to {}", args.receipt_file);
 
     Ok(())
 }
@@ -198,16 +535,28 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let args = Args::parse();
 
-    if args.generate_proof {
-        generate_zkverify_proof()?;
-    }
+    // A templated name is only known once the proof is generated
+    let proof_file = if args.generate_proof {
+        generate_zkverify_proof(args.proof_mode, &args.output_file)?
+    } else {
+        PathBuf::from(&args.output_file)
+    };
 
     if args.verify_locally {
-        verify_local_proof()?;
+        verify_local_proof(&proof_file)?;
+    }
+
+    if let Some(path) = &args.verify_file {
+        verify_local_proof(Path::new(path))?;
+    }
+
+    if args.submit {
+        submit_proof(&args, &proof_file)?;
     }
 
-    if !args.generate_proof && !args.verify_locally {
-        println!("Usage: cargo run --bin zkverify -- --generate-proof [--verify-locally]");
+    if !args.generate_proof && !args.verify_locally && args.verify_file.is_none() && !args.submit {
+        println!("Usage: cargo run --bin zkverify -- --generate-proof [--proof-mode compressed|plonk] [--verify-locally] [--submit --rpc-url <url>]");
+        println!("       cargo run --bin zkverify -- --verify-file <proof file>");
     }
 
     Ok(())
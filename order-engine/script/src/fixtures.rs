@@ -0,0 +1,90 @@
+//! JSON fixtures of the nullifier-flow demo, for client libraries to test against without
+//! rerunning it
+
+use fibonacci_lib::{
+    verify_commitment_merkle_proof, verify_tree_root, Hash32, MerkleProof, NullifierData,
+    OrderCommitment,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// The demo's final commitment tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeFixture {
+    /// Leaves in tree order
+    pub leaves: Vec<[u8; 32]>,
+    pub root: [u8; 32],
+}
+
+/// One user's order commitment, including its private nullifier and balance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserFixture {
+    pub name: String,
+    pub commitment: OrderCommitment,
+    pub nullifier: NullifierData,
+}
+
+/// A Merkle proof the demo generated, with the root it was generated against (not always the
+/// final one)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofFixture {
+    pub name: String,
+    pub leaf: [u8; 32],
+    pub root: [u8; 32],
+    pub proof: MerkleProof,
+}
+
+/// Everything `demo --export-fixtures` writes, one JSON file per field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DemoFixtures {
+    pub tree: TreeFixture,
+    pub users: Vec<UserFixture>,
+    pub proofs: Vec<ProofFixture>,
+}
+
+impl DemoFixtures {
+    pub const TREE_FILE: &'static str = "tree.json";
+    pub const USERS_FILE: &'static str = "users.json";
+    pub const PROOFS_FILE: &'static str = "proofs.json";
+
+    /// Writes the fixture files into `dir`, creating it if needed
+    pub fn write(&self, dir: &Path) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(dir)?;
+        fs::write(
+            dir.join(Self::TREE_FILE),
+            serde_json::to_string_pretty(&self.tree)?,
+        )?;
+        fs::write(
+            dir.join(Self::USERS_FILE),
+            serde_json::to_string_pretty(&self.users)?,
+        )?;
+        fs::write(
+            dir.join(Self::PROOFS_FILE),
+            serde_json::to_string_pretty(&self.proofs)?,
+        )?;
+        Ok(())
+    }
+
+    /// Reads fixture files written by `write`
+    pub fn load(dir: &Path) -> Result<Self, Box<dyn Error>> {
+        fn read<T: DeserializeOwned>(path: &Path) -> Result<T, Box<dyn Error>> {
+            Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+        }
+
+        Ok(Self {
+            tree: read(&dir.join(Self::TREE_FILE))?,
+            users: read(&dir.join(Self::USERS_FILE))?,
+            proofs: read(&dir.join(Self::PROOFS_FILE))?,
+        })
+    }
+
+    /// Whether the leaves still fold to the tree root and every proof to its recorded root
+    pub fn verify(&self) -> bool {
+        verify_tree_root(&self.tree.leaves, &Hash32(self.tree.root))
+            && self.proofs.iter().all(|fixture| {
+                verify_commitment_merkle_proof(&fixture.leaf, &fixture.proof, &Hash32(fixture.root))
+            })
+    }
+}
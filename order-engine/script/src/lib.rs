@@ -0,0 +1,79 @@
+//! Helpers shared by the dark pool script binaries
+
+pub mod bench;
+pub mod fixtures;
+pub mod matching;
+#[cfg(feature = "sqlite")]
+pub mod store;
+
+use fibonacci_lib::{AmountRepr, OrderDomain};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Deployment every demo order is committed for (Holesky)
+pub const DEMO_DOMAIN: OrderDomain = OrderDomain {
+    chain_id: 17000,
+    pool_address: [0xCu8; 20],
+    amount_repr: AmountRepr::U64,
+};
+
+/// Address demo proofs commit as the protocol fee recipient
+pub const DEMO_FEE_RECIPIENT: [u8; 20] = [0xFu8; 20];
+
+/// How long demo orders stay open past their market timestamp
+pub const DEMO_ORDER_LIFETIME_SECS: u64 = 3600;
+
+/// Market timestamp for demo orders: `DEMO_BLOCK_TIMESTAMP` when set, otherwise now, so the demo
+/// orders never go stale
+pub fn demo_block_timestamp() -> Result<u64, Box<dyn Error>> {
+    match env_u64("DEMO_BLOCK_TIMESTAMP")? {
+        Some(timestamp) => Ok(timestamp),
+        None => Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()),
+    }
+}
+
+/// Deadline for demo orders: `DEMO_DEADLINE` when set, otherwise `DEMO_ORDER_LIFETIME_SECS` after
+/// `block_timestamp`
+pub fn demo_deadline(block_timestamp: u64) -> Result<u64, Box<dyn Error>> {
+    Ok(env_u64("DEMO_DEADLINE")?.unwrap_or(block_timestamp + DEMO_ORDER_LIFETIME_SECS))
+}
+
+/// Order book at `DEMO_ORDER_DB` for the relayer matching demo, or `None` to keep it in memory
+/// (unset or empty)
+#[cfg(feature = "sqlite")]
+pub fn demo_order_store() -> Result<Option<store::Store>, Box<dyn Error>> {
+    match env::var("DEMO_ORDER_DB") {
+        Ok(path) if !path.trim().is_empty() => Ok(Some(store::Store::open(path)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Output file path from `template`, with `{vkey}` replaced by `vkey` and `{timestamp}` by the
+/// current Unix time (e.g. `proofs/proof_{vkey}_{timestamp}.json`). Creates the parent
+/// directories.
+pub fn output_path(template: &str, vkey: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let path = PathBuf::from(
+        template
+            .replace("{vkey}", vkey)
+            .replace("{timestamp}", &timestamp.to_string()),
+    );
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(path)
+}
+
+/// `name` parsed from the environment; unset and empty (as in `.env.example`) are both `None`
+fn env_u64(name: &str) -> Result<Option<u64>, Box<dyn Error>> {
+    match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => value
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("invalid {} '{}': {}", name, value, e).into()),
+        _ => Ok(None),
+    }
+}
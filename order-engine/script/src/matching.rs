@@ -0,0 +1,117 @@
+//! Relayer-side order matching
+//!
+//! Orders are matched whole (no partial fills) and priced the way the guest validates them:
+//! `target_price` is the minimum `token_out` per `PRICE_SCALE` `token_in` for every order, so a
+//! sell of the base token quotes the other token per base and a buy quotes base per other token.
+//!
+//! A whole match has no price of its own to choose: each leg receives the other leg's input, so
+//! the quantities fix the price each leg executes at. A pair matches only if both legs pass
+//! `validate_order` at those prices, which rejects pairs whose quantities do not fit both limits.
+
+use fibonacci_lib::{
+    hash_order, validate_order, MarketConditions, OrderData, OrderKind, PRICE_SCALE,
+};
+use std::collections::HashSet;
+
+/// An order resting in the relayer's book, keyed by its public nullifier hash
+#[derive(Debug, Clone)]
+pub struct BookOrder {
+    pub owner: String,
+    pub nullifier_hash: [u8; 32],
+    pub order: OrderData,
+}
+
+/// A crossing sell/buy pair, identified by the orders' nullifier hashes, with the market each
+/// leg executes (and is proven) against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderMatch {
+    pub sell: [u8; 32],
+    pub buy: [u8; 32],
+    /// Other token per base: the buy's input over the sell's
+    pub sell_market: MarketConditions,
+    /// Base per other token: the sell's input over the buy's
+    pub buy_market: MarketConditions,
+}
+
+/// Prices at which `sell` and `buy` exchange their whole inputs, as `(sell leg, buy leg)`:
+/// each leg's output is the other's input, rounded down. `None` when they do not fit a `u64`.
+pub fn leg_prices(sell: &OrderData, buy: &OrderData) -> Option<(u64, u64)> {
+    let price = |amount_out: u64, amount_in: u64| {
+        (amount_in != 0)
+            .then(|| amount_out as u128 * PRICE_SCALE / amount_in as u128)
+            .and_then(|price| u64::try_from(price).ok())
+    };
+    Some((
+        price(buy.amount_in, sell.amount_in)?,
+        price(sell.amount_in, buy.amount_in)?,
+    ))
+}
+
+/// Matches exact-in sells of `base` (`token_in == base`) against exact-in buys of it
+/// (`token_out == base`), lowest asks and highest bids first, validating both legs at
+/// `block_timestamp`. No reference price is supplied, so orders capping price impact never match.
+///
+/// Orders whose nullifier is in `spent` are skipped and no order is matched twice. Callers mark
+/// both nullifiers of a match spent once it settles.
+pub fn match_orders(
+    book: &[BookOrder],
+    base: [u8; 20],
+    block_timestamp: u64,
+    spent: &HashSet<[u8; 32]>,
+) -> Vec<OrderMatch> {
+    let open =
+        |o: &&BookOrder| o.order.kind == OrderKind::ExactIn && !spent.contains(&o.nullifier_hash);
+    let mut sells: Vec<&BookOrder> = book
+        .iter()
+        .filter(|o| o.order.token_in == base)
+        .filter(open)
+        .collect();
+    let mut buys: Vec<&BookOrder> = book
+        .iter()
+        .filter(|o| o.order.token_out == base)
+        .filter(open)
+        .collect();
+
+    // Lowest asks first; the highest bid is the lowest minimum of base per other token
+    sells.sort_by_key(|o| o.order.target_price);
+    buys.sort_by_key(|o| o.order.target_price);
+
+    let market = |current_price| MarketConditions {
+        current_price,
+        block_timestamp,
+        reference_price: 0,
+    };
+    let executes = |order: &OrderData, market: &MarketConditions| {
+        validate_order(order, market, &hash_order(order)).is_ok()
+    };
+
+    let mut matched = HashSet::new();
+    let mut matches = Vec::new();
+    for sell in sells {
+        let counterparty =
+            buys.iter().find_map(|buy| {
+                if matched.contains(&buy.nullifier_hash)
+                    || buy.order.token_in != sell.order.token_out
+                    || buy.order.domain != sell.order.domain
+                {
+                    return None;
+                }
+                let (sell_price, buy_price) = leg_prices(&sell.order, &buy.order)?;
+                let (sell_market, buy_market) = (market(sell_price), market(buy_price));
+                (executes(&sell.order, &sell_market) && executes(&buy.order, &buy_market))
+                    .then_some((buy, sell_market, buy_market))
+            });
+
+        if let Some((buy, sell_market, buy_market)) = counterparty {
+            matched.insert(buy.nullifier_hash);
+            matches.push(OrderMatch {
+                sell: sell.nullifier_hash,
+                buy: buy.nullifier_hash,
+                sell_market,
+                buy_market,
+            });
+        }
+    }
+
+    matches
+}
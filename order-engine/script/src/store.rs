@@ -0,0 +1,189 @@
+//! SQLite-backed order book so a relayer's orders, commitments and spent nullifiers survive
+//! restarts (feature `sqlite`)
+
+use fibonacci_lib::{hash_order, NullifierData, OrderData};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+/// One persisted order; everything stored is public or relayer-side metadata (no secrets)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredOrder {
+    pub commitment_hash: [u8; 32],
+    pub nullifier_hash: [u8; 32],
+    pub order_hash: [u8; 32],
+    pub wallet: [u8; 20],
+    pub deadline: u64,
+    pub spent: bool,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Opens (or creates) the database at `path`
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Opens a throwaway database that lives as long as the `Store`
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS orders (
+                commitment_hash BLOB PRIMARY KEY,
+                nullifier_hash  BLOB NOT NULL UNIQUE,
+                order_hash      BLOB NOT NULL,
+                wallet          BLOB NOT NULL,
+                deadline        INTEGER NOT NULL,
+                spent           INTEGER NOT NULL DEFAULT 0
+            );",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Persists a committed order. Re-inserting the same commitment is a no-op.
+    pub fn insert_order(
+        &self,
+        order: &OrderData,
+        nullifier_data: &NullifierData,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO orders
+                (commitment_hash, nullifier_hash, order_hash, wallet, deadline)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                &nullifier_data.commitment_hash[..],
+                &nullifier_data.nullifier_hash[..],
+                &hash_order(order)[..],
+                &order.wallet_address[..],
+                order.deadline as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Marks the order with `nullifier_hash` as spent. Returns `false` if it was unknown or
+    /// already spent.
+    pub fn mark_spent(&self, nullifier_hash: &[u8; 32]) -> rusqlite::Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE orders SET spent = 1 WHERE nullifier_hash = ?1 AND spent = 0",
+            params![&nullifier_hash[..]],
+        )?;
+        Ok(updated == 1)
+    }
+
+    /// Whether `nullifier_hash` belongs to a spent order
+    pub fn is_spent(&self, nullifier_hash: &[u8; 32]) -> rusqlite::Result<bool> {
+        let spent: Option<bool> = self
+            .conn
+            .query_row(
+                "SELECT spent FROM orders WHERE nullifier_hash = ?1",
+                params![&nullifier_hash[..]],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(spent.unwrap_or(false))
+    }
+
+    /// Unspent orders, in insertion order
+    pub fn active_orders(&self) -> rusqlite::Result<Vec<StoredOrder>> {
+        self.query("WHERE spent = 0")
+    }
+
+    /// Every stored commitment (spent or not), in insertion order, for rebuilding the tree
+    pub fn commitment_hashes(&self) -> rusqlite::Result<Vec<[u8; 32]>> {
+        Ok(self
+            .query("")?
+            .into_iter()
+            .map(|order| order.commitment_hash)
+            .collect())
+    }
+
+    fn query(&self, filter: &str) -> rusqlite::Result<Vec<StoredOrder>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT commitment_hash, nullifier_hash, order_hash, wallet, deadline, spent
+             FROM orders {filter} ORDER BY rowid"
+        ))?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(StoredOrder {
+                commitment_hash: blob(row.get(0)?, 0)?,
+                nullifier_hash: blob(row.get(1)?, 1)?,
+                order_hash: blob(row.get(2)?, 2)?,
+                wallet: blob(row.get(3)?, 3)?,
+                deadline: row.get::<_, i64>(4)? as u64,
+                spent: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
+
+/// Converts a BLOB column into a fixed-size array
+fn blob<const N: usize>(bytes: Vec<u8>, column: usize) -> rusqlite::Result<[u8; N]> {
+    let len = bytes.len();
+    bytes.try_into().map_err(|_| {
+        rusqlite::Error::FromSqlConversionFailure(
+            column,
+            rusqlite::types::Type::Blob,
+            format!("expected {N} bytes, got {len}").into(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEMO_DOMAIN;
+    use fibonacci_lib::{create_order_commitment, OrderKind};
+    use std::{env, fs, process};
+
+    fn order(wallet: u8) -> OrderData {
+        OrderData {
+            domain: DEMO_DOMAIN,
+            wallet_address: [wallet; 20],
+            token_in: [0xAu8; 20],
+            token_out: [0xBu8; 20],
+            amount_in: 5_000_000_000_000_000_000,
+            min_amount_out: 10_000_000_000,
+            target_price: 2_000_000_000,
+            deadline: 2000,
+            kind: OrderKind::ExactIn,
+            max_price_impact_bps: None,
+            fee_bps: 0,
+            group_id: None,
+        }
+    }
+
+    #[test]
+    fn spent_order_stays_inactive_after_reopening() {
+        let path = env::temp_dir().join(format!("order-store-{}.sqlite", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let (spent, kept) = (order(1), order(2));
+        let (_, spent_data) = create_order_commitment(&spent, &[1u8; 32], 1, &hash_order(&spent));
+        let (_, kept_data) = create_order_commitment(&kept, &[2u8; 32], 1, &hash_order(&kept));
+        {
+            let store = Store::open(&path).unwrap();
+            store.insert_order(&spent, &spent_data).unwrap();
+            store.insert_order(&kept, &kept_data).unwrap();
+            assert!(store.mark_spent(&spent_data.nullifier_hash).unwrap());
+        }
+
+        let store = Store::open(&path).unwrap();
+        let active = store.active_orders().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].nullifier_hash, kept_data.nullifier_hash);
+        assert!(store.is_spent(&spent_data.nullifier_hash).unwrap());
+        assert_eq!(
+            store.commitment_hashes().unwrap(),
+            [spent_data.commitment_hash, kept_data.commitment_hash]
+        );
+    }
+}
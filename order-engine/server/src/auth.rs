@@ -0,0 +1,35 @@
+//! ────────────────  Bearer-token guard for operator endpoints  ────────────────
+
+use axum::http::{HeaderMap, StatusCode, header::AUTHORIZATION};
+
+/// Checks `Authorization: Bearer <token>` against the configured `API_TOKEN`.
+///
+/// Guarded endpoints are disabled outright when no token is configured.
+pub fn require_bearer(
+    headers: &HeaderMap,
+    expected: Option<&str>,
+) -> Result<(), (StatusCode, String)> {
+    let Some(expected) = expected else {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "endpoint disabled: API_TOKEN is not configured".to_string(),
+        ));
+    };
+
+    let provided = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(()),
+        _ => Err((
+            StatusCode::UNAUTHORIZED,
+            "missing or invalid bearer token".to_string(),
+        )),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
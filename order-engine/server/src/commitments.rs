@@ -0,0 +1,154 @@
+//! ────────────────  Server-maintained commitment tree  ────────────────
+//!
+//! Keeps the relayer's `CommitmentMerkleTree`, a window of recent roots (proofs against any of
+//! them are still accepted by clients) and a cache of inclusion proofs against the current root,
+//! keyed by commitment hash. Every insert changes the root, so it empties the cache.
+//!
+//! With `COMMITMENT_SNAPSHOT` set, the leaves are saved after every insert and the tree is
+//! rebuilt from them at startup and by `POST /admin/rebuild-tree`. The snapshot is the raw
+//! 32-byte leaves back to back in tree order, with no header.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io,
+    path::Path,
+};
+
+use fibonacci_lib::CommitmentMerkleTree;
+
+use crate::nullifiers::tmp_path;
+
+/// Inclusion proof in the split form the guest reads: `(siblings, indices)`.
+pub type InclusionProof = (Vec<[u8; 32]>, Vec<u8>);
+
+pub struct CommitmentStore {
+    tree: CommitmentMerkleTree<()>,
+    current_root: [u8; 32],
+    root_history: VecDeque<[u8; 32]>,
+    root_history_size: usize,
+    proof_cache: HashMap<[u8; 32], InclusionProof>,
+    cache_order: VecDeque<[u8; 32]>, // insertion order, oldest first
+    proof_cache_size: usize,
+    cache_hits: u64,
+    cache_misses: u64,
+}
+
+impl CommitmentStore {
+    pub fn new(root_history_size: usize, proof_cache_size: usize) -> Self {
+        Self {
+            tree: CommitmentMerkleTree::new(),
+            current_root: [0u8; 32],
+            root_history: VecDeque::new(),
+            root_history_size: root_history_size.max(1),
+            proof_cache: HashMap::new(),
+            cache_order: VecDeque::new(),
+            proof_cache_size,
+            cache_hits: 0,
+            cache_misses: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn current_root(&self) -> [u8; 32] {
+        self.current_root
+    }
+
+    /// Whether `root` is the current root or still in the history window
+    pub fn is_recent_root(&self, root: &[u8; 32]) -> bool {
+        self.root_history.contains(root)
+    }
+
+    /// Appends a commitment and returns the new root. Fails if it is already in the tree or the
+    /// tree is full.
+    ///
+    /// Cached proofs are against the previous root, so the proof cache is emptied.
+    pub fn insert(&mut self, commitment_hash: [u8; 32]) -> Result<[u8; 32], String> {
+        self.tree
+            .add_commitment(commitment_hash, ())
+            .map_err(|e| e.to_string())?;
+        let (root, _) = self.tree.build_tree().map_err(|e| e.to_string())?;
+        self.current_root = root;
+        self.proof_cache.clear();
+        self.cache_order.clear();
+
+        self.root_history.push_back(root);
+        if self.root_history.len() > self.root_history_size {
+            self.root_history.pop_front();
+        }
+
+        Ok(root)
+    }
+
+    /// Returns the inclusion proof of `commitment_hash` against the current root, and whether it
+    /// was served from the cache. `None` if the commitment is not in the tree.
+    pub fn proof(&mut self, commitment_hash: [u8; 32]) -> Option<(InclusionProof, bool)> {
+        if let Some(proof) = self.proof_cache.get(&commitment_hash) {
+            self.cache_hits += 1;
+            return Some((proof.clone(), true));
+        }
+
+        let proof = self.tree.generate_proof(commitment_hash).ok()?.into_parts();
+        self.cache_misses += 1;
+        if self.proof_cache_size > 0 {
+            if self.proof_cache.len() >= self.proof_cache_size
+                && let Some(oldest) = self.cache_order.pop_front()
+            {
+                self.proof_cache.remove(&oldest);
+            }
+            self.proof_cache.insert(commitment_hash, proof.clone());
+            self.cache_order.push_back(commitment_hash);
+        }
+
+        Some((proof, false))
+    }
+
+    /// Replaces the tree with `leaves`, in tree order, and returns the new root. The root history
+    /// restarts at that root and the proof cache is emptied. On error the store is unchanged.
+    pub fn rebuild(&mut self, leaves: &[[u8; 32]]) -> Result<[u8; 32], String> {
+        let mut tree = CommitmentMerkleTree::new();
+        for leaf in leaves {
+            tree.add_commitment(*leaf, ()).map_err(|e| e.to_string())?;
+        }
+        // An empty store's root is all zero, as before the first insert
+        let root = tree.build_tree().map_or([0u8; 32], |(root, _)| root);
+
+        self.tree = tree;
+        self.current_root = root;
+        self.root_history = VecDeque::from([root]);
+        self.proof_cache.clear();
+        self.cache_order.clear();
+        Ok(root)
+    }
+
+    /// Writes the leaves to a temporary file next to `path`, then renames it over `path`, so a
+    /// crash mid-write leaves the previous snapshot intact.
+    pub fn save_to_file(&self, path: &Path) -> io::Result<()> {
+        let tmp = tmp_path(path);
+        fs::write(&tmp, self.tree.leaves().concat())?;
+        fs::rename(&tmp, path)
+    }
+
+    /// Reads the leaves of a snapshot written by `save_to_file`, for `rebuild`. Fails with
+    /// `InvalidData` if its length is not a multiple of 32 bytes.
+    pub fn load_leaves(path: &Path) -> io::Result<Vec<[u8; 32]>> {
+        let bytes = fs::read(path)?;
+        if bytes.len() % 32 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "commitment snapshot is {} bytes, not a multiple of 32",
+                    bytes.len()
+                ),
+            ));
+        }
+        Ok(bytes.as_chunks::<32>().0.to_vec())
+    }
+
+    /// Proofs served from the cache and generated, since startup
+    pub fn cache_stats(&self) -> (u64, u64) {
+        (self.cache_hits, self.cache_misses)
+    }
+}
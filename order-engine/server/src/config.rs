@@ -0,0 +1,257 @@
+//! ────────────────  Server configuration (loaded from the environment)  ────────────────
+
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail};
+use fibonacci_lib::{MAX_BATCH_SIZE, NullifierBits, TokenAddress, TokenConfig, TokenRegistry};
+
+use crate::logging::LogFormat;
+
+/// Which SP1 prover the server talks to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProverBackend {
+    /// Prove on this machine's CPU.
+    Local,
+    /// Prove on the Succinct Prover Network (requires `NETWORK_PRIVATE_KEY`).
+    Network,
+    /// SP1's mock prover: executes the guest for real outputs but returns a mock proof that
+    /// verifies without being one. Test builds only.
+    #[cfg(feature = "test-mock-prover")]
+    Mock,
+}
+
+impl ProverBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProverBackend::Local => "local",
+            ProverBackend::Network => "network",
+            #[cfg(feature = "test-mock-prover")]
+            ProverBackend::Mock => "mock",
+        }
+    }
+}
+
+impl FromStr for ProverBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(ProverBackend::Local),
+            "network" => Ok(ProverBackend::Network),
+            #[cfg(feature = "test-mock-prover")]
+            "mock" => Ok(ProverBackend::Mock),
+            other => Err(anyhow!(
+                "invalid PROVER_BACKEND '{other}', expected \"local\" or \"network\""
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ProverBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub prover_backend: ProverBackend,
+    /// Only set (and required) for the network backend.
+    pub network_private_key: Option<String>,
+    /// Number of recent tree roots whose inclusion proofs stay cached.
+    pub root_history_size: usize,
+    /// Maximum number of cached inclusion proofs (0 disables the cache).
+    pub proof_cache_size: usize,
+    /// Maximum age of `market.block_timestamp` accepted by `/prove` (0 disables the check).
+    pub freshness_window_secs: u64,
+    /// Minimum time an order's `deadline` must lie beyond `market.block_timestamp` (0 only
+    /// rejects already expired orders).
+    pub deadline_buffer_secs: u64,
+    /// Bearer token for operator endpoints; they are disabled when unset.
+    pub api_token: Option<String>,
+    /// How long submitted requests stay available to `/prove/reprice`.
+    pub request_ttl_secs: u64,
+    /// How long a `/prove` response is returned again for a repeated `Idempotency-Key`.
+    pub idempotency_ttl_secs: u64,
+    /// Maximum number of calls in one `/rpc` batch.
+    pub rpc_max_batch: usize,
+    /// Maximum number of orders in one batch proof or `/rpc` batch, reported at `/program`; at
+    /// most the guest's `MAX_BATCH_SIZE`.
+    pub max_batch_size: usize,
+    /// Number of proofs generated at once; further requests wait, highest tip first.
+    pub prove_slots: usize,
+    /// Directory `/prove` requests are logged to for replay; logging is off when unset.
+    pub request_log_dir: Option<String>,
+    /// Number of newest request logs kept.
+    pub request_log_max_files: usize,
+    /// Secret the request log keys its wallet hashes with; required with `request_log_dir`.
+    pub request_log_key: Option<String>,
+    /// Log line format; levels are set with `RUST_LOG`.
+    pub log_format: LogFormat,
+    /// Directory of older guest ELFs served alongside the compiled-in one.
+    pub program_dir: Option<String>,
+    /// Width of the nullifier hashes clients send and proofs commit.
+    pub nullifier_bits: NullifierBits,
+    /// Address proofs commit as the protocol fee recipient.
+    pub fee_recipient: [u8; 20],
+    /// File the cancelled nullifier set is saved to and reloaded from; in memory only when unset.
+    pub nullifier_snapshot: Option<PathBuf>,
+    /// JSON file of per-token decimals and quote scales for quoted market prices.
+    pub token_config: Option<PathBuf>,
+    /// File the commitment tree's leaves are saved to and rebuilt from; in memory only when unset.
+    pub commitment_snapshot: Option<PathBuf>,
+    /// Prove a fixed input at startup, so the server can become ready before its first `/prove`.
+    pub startup_self_test: bool,
+    /// Maximum age of the committed market timestamp for the `/rpc` verify call to report a
+    /// proof fresh (0 disables the check).
+    pub verify_max_age_secs: u64,
+    /// Reject stale proofs in the verify call with 409 instead of flagging them.
+    pub verify_strict_age: bool,
+}
+
+impl Config {
+    /// Reads the configuration from the environment.
+    ///
+    /// - `PROVER_BACKEND`: `local` (default) or `network`
+    /// - `NETWORK_PRIVATE_KEY`: whitelisted key, required when `PROVER_BACKEND=network`
+    /// - `ROOT_HISTORY_SIZE`: recent roots kept in the proof cache window (default 32)
+    /// - `PROOF_CACHE_SIZE`: maximum cached inclusion proofs (default 1024)
+    /// - `FRESHNESS_WINDOW_SECS`: maximum market data age for `/prove` (default 300)
+    /// - `DEADLINE_BUFFER_SECS`: minimum order lifetime past the market timestamp (default 60)
+    /// - `API_TOKEN`: bearer token for guarded endpoints such as `/prove/reprice`
+    /// - `REQUEST_TTL_SECS`: lifetime of retained request state (default 120)
+    /// - `IDEMPOTENCY_TTL_SECS`: lifetime of `Idempotency-Key` results (default 3600)
+    /// - `RPC_MAX_BATCH`: maximum calls per `/rpc` batch (default 16)
+    /// - `MAX_BATCH_SIZE`: maximum orders per batch proof or `/rpc` batch (default and upper
+    ///   bound `fibonacci_lib::MAX_BATCH_SIZE`, 32)
+    /// - `PROVE_SLOTS`: concurrent proofs (default 1); waiting requests are ordered by tip
+    /// - `REQUEST_LOG_DIR`: directory for replayable `/prove` request logs
+    /// - `REQUEST_LOG_MAX_FILES`: request logs kept before the oldest are deleted (default 1000)
+    /// - `REQUEST_LOG_KEY`: secret of at least 16 bytes keying the logged wallet hashes; required
+    ///   with `REQUEST_LOG_DIR`
+    /// - `LOG_FORMAT`: `pretty` (default) or `json`
+    /// - `PROGRAM_DIR`: directory of additional guest ELFs named `<name>-v<protocol version>`,
+    ///   selected per request by vkey hash; all must be at the current protocol version
+    /// - `NULLIFIER_BITS`: `256` (default) or `128` for settlement storing `uint128` hashes
+    /// - `FEE_RECIPIENT`: 20-byte hex address paid the protocol fee (default the zero address)
+    /// - `NULLIFIER_SNAPSHOT`: file persisting cancelled nullifier hashes across restarts
+    /// - `TOKEN_CONFIG`: JSON file of token decimals and quote scales (see `load_token_registry`)
+    /// - `COMMITMENT_SNAPSHOT`: file persisting the commitment tree's leaves across restarts
+    /// - `STARTUP_SELF_TEST`: `true` to prove a fixed input at startup (default `false`)
+    /// - `VERIFY_MAX_AGE_SECS`: maximum committed market data age for the verify call (default 0,
+    ///   off)
+    /// - `VERIFY_STRICT_AGE`: `true` to answer stale proofs with 409 (default `false`)
+    pub fn from_env() -> anyhow::Result<Self> {
+        let prover_backend = match env::var("PROVER_BACKEND") {
+            Ok(value) => value.parse()?,
+            Err(_) => ProverBackend::Local,
+        };
+
+        let network_private_key = env::var("NETWORK_PRIVATE_KEY")
+            .ok()
+            .filter(|key| !key.trim().is_empty());
+
+        if prover_backend == ProverBackend::Network && network_private_key.is_none() {
+            bail!("PROVER_BACKEND=network requires NETWORK_PRIVATE_KEY to be set");
+        }
+
+        let max_batch_size = parse_env("MAX_BATCH_SIZE", MAX_BATCH_SIZE)?;
+        if max_batch_size == 0 || max_batch_size > MAX_BATCH_SIZE {
+            bail!(
+                "MAX_BATCH_SIZE must be between 1 and {MAX_BATCH_SIZE}, the most the guest accepts"
+            );
+        }
+
+        Ok(Config {
+            prover_backend,
+            network_private_key: network_private_key
+                .filter(|_| prover_backend == ProverBackend::Network),
+            root_history_size: parse_env("ROOT_HISTORY_SIZE", 32)?,
+            proof_cache_size: parse_env("PROOF_CACHE_SIZE", 1024)?,
+            freshness_window_secs: parse_env("FRESHNESS_WINDOW_SECS", 300)?,
+            deadline_buffer_secs: parse_env("DEADLINE_BUFFER_SECS", 60)?,
+            api_token: env::var("API_TOKEN")
+                .ok()
+                .filter(|token| !token.trim().is_empty()),
+            request_ttl_secs: parse_env("REQUEST_TTL_SECS", 120)?,
+            idempotency_ttl_secs: parse_env("IDEMPOTENCY_TTL_SECS", 3600)?,
+            rpc_max_batch: parse_env("RPC_MAX_BATCH", 16)?,
+            max_batch_size,
+            prove_slots: parse_env("PROVE_SLOTS", 1)?,
+            request_log_dir: env::var("REQUEST_LOG_DIR")
+                .ok()
+                .filter(|dir| !dir.trim().is_empty()),
+            request_log_max_files: parse_env("REQUEST_LOG_MAX_FILES", 1000)?,
+            request_log_key: env::var("REQUEST_LOG_KEY")
+                .ok()
+                .filter(|key| !key.trim().is_empty()),
+            log_format: parse_env("LOG_FORMAT", LogFormat::Pretty)?,
+            program_dir: env::var("PROGRAM_DIR")
+                .ok()
+                .filter(|dir| !dir.trim().is_empty()),
+            nullifier_bits: parse_env("NULLIFIER_BITS", NullifierBits::Bits256)?,
+            fee_recipient: match env::var("FEE_RECIPIENT") {
+                Ok(value) if !value.trim().is_empty() => parse_address("FEE_RECIPIENT", &value)?,
+                _ => [0u8; 20],
+            },
+            nullifier_snapshot: env::var("NULLIFIER_SNAPSHOT")
+                .ok()
+                .filter(|path| !path.trim().is_empty())
+                .map(PathBuf::from),
+            token_config: env::var("TOKEN_CONFIG")
+                .ok()
+                .filter(|path| !path.trim().is_empty())
+                .map(PathBuf::from),
+            commitment_snapshot: env::var("COMMITMENT_SNAPSHOT")
+                .ok()
+                .filter(|path| !path.trim().is_empty())
+                .map(PathBuf::from),
+            startup_self_test: parse_env("STARTUP_SELF_TEST", false)?,
+            verify_max_age_secs: parse_env("VERIFY_MAX_AGE_SECS", 0)?,
+            verify_strict_age: parse_env("VERIFY_STRICT_AGE", false)?,
+        })
+    }
+}
+
+/// Reads a `TokenRegistry` from a JSON object keyed by token address:
+/// `{"0xA0b8…": {"decimals": 6, "quote_decimals": 6}, …}`.
+pub fn load_token_registry(path: &Path) -> anyhow::Result<TokenRegistry> {
+    let file =
+        fs::read(path).map_err(|e| anyhow!("cannot read TOKEN_CONFIG {}: {e}", path.display()))?;
+    let entries: BTreeMap<String, TokenConfig> = serde_json::from_slice(&file)
+        .map_err(|e| anyhow!("invalid TOKEN_CONFIG {}: {e}", path.display()))?;
+
+    let mut tokens = TokenRegistry::new();
+    for (address, config) in entries {
+        let token = parse_address("TOKEN_CONFIG address", &address)?;
+        tokens.insert(TokenAddress(token), config);
+    }
+    Ok(tokens)
+}
+
+/// Parses a 0x-prefixed (or bare) 20-byte hex address.
+fn parse_address(name: &str, value: &str) -> anyhow::Result<[u8; 20]> {
+    hex::decode(value.trim().trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("invalid {name} '{value}': expected a 20-byte hex address"))
+}
+
+/// Parses `name` from the environment, falling back to `default` when unset.
+fn parse_env<T>(name: &str, default: T) -> anyhow::Result<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    match env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map_err(|e| anyhow!("invalid {name} '{value}': {e}")),
+        Err(_) => Ok(default),
+    }
+}
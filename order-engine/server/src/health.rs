@@ -0,0 +1,210 @@
+//! ────────────────  Liveness and deep health checks  ────────────────
+//!
+//! `/health` only reports that the server is up. `/health?deep=true` additionally executes the
+//! guest on a fixed input, catching a broken ELF or a misconfigured prover backend. The deep
+//! result is cached for `DEEP_CHECK_TTL` so frequent probes don't each run the zkVM.
+//!
+//! `/health?ready=true` is the readiness probe: it fails until a proof has been generated and
+//! verified, by a `/prove` or by the `STARTUP_SELF_TEST` prove, since key setup succeeding does
+//! not show the prover backend works end to end.
+
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use fibonacci_lib::{
+    AmountRepr, DisclosureLevel, MarketConditions, NullifierBits, OrderData, OrderDomain,
+    OrderKind, OrderProofInputs, build_stdin,
+};
+use serde::{Deserialize, Serialize};
+use sp1_sdk::SP1ProofMode;
+
+use crate::AppState;
+
+const DEEP_CHECK_TTL: Duration = Duration::from_secs(30);
+
+/// Outcome of the last deep check and when it ran.
+pub type DeepCheckCache = Option<(Instant, Result<u64, String>)>;
+
+#[derive(Deserialize)]
+pub struct HealthQuery {
+    #[serde(default)]
+    deep: bool,
+    /// Also fail until the first proof succeeded
+    #[serde(default)]
+    ready: bool,
+}
+
+#[derive(Serialize)]
+pub struct HealthResponse {
+    status: &'static str,
+    prover_backend: &'static str,
+    prove_queue_waiting: usize,
+    ready: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deep: Option<DeepCheck>,
+}
+
+#[derive(Serialize)]
+struct DeepCheck {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cycles: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    age_secs: u64,
+}
+
+pub async fn health_handler(
+    State(state): State<AppState>,
+    Query(query): Query<HealthQuery>,
+) -> (StatusCode, Json<HealthResponse>) {
+    let deep = if query.deep {
+        Some(deep_check(&state).await)
+    } else {
+        None
+    };
+    let ready = state.first_prove_succeeded.load(Ordering::Relaxed);
+    let healthy = deep.as_ref().is_none_or(|check| check.ok) && (ready || !query.ready);
+
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(HealthResponse {
+            status: if healthy { "ok" } else { "unhealthy" },
+            prover_backend: state.backend.as_str(),
+            prove_queue_waiting: state.prove_queue.waiting(),
+            ready,
+            deep,
+        }),
+    )
+}
+
+/// Returns the cached deep check, re-running it once the cache has expired. The execute runs on
+/// the blocking pool; concurrent probes wait on the async lock and share its result.
+async fn deep_check(state: &AppState) -> DeepCheck {
+    let mut cache = state.deep_health.lock().await;
+
+    let (checked_at, result) = match cache.as_ref() {
+        Some((checked_at, result)) if checked_at.elapsed() < DEEP_CHECK_TTL => {
+            (*checked_at, result.clone())
+        }
+        _ => {
+            let executor = state.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                let stdin = build_stdin(&probe_inputs()).map_err(|e| e.to_string())?;
+                executor
+                    .client
+                    .execute(&executor.programs.latest().elf, &stdin)
+                    .map(|(_, report)| report.total_instruction_count())
+                    .map_err(|e| e.to_string())
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .flatten();
+            *cache = Some((Instant::now(), result.clone()));
+            (Instant::now(), result)
+        }
+    };
+
+    DeepCheck {
+        ok: result.is_ok(),
+        cycles: result.as_ref().ok().copied(),
+        error: result.err(),
+        age_secs: checked_at.elapsed().as_secs(),
+    }
+}
+
+/// Proves and verifies `probe_inputs` on the latest program, taking a proving slot like any
+/// request, and marks the server ready if that works. Takes as long as a full Groth16 prove.
+pub async fn startup_self_test(state: AppState) {
+    let result = match build_stdin(&probe_inputs()) {
+        Ok(stdin) => {
+            let prover = state.clone();
+            state
+                .prove_queue
+                .run(0, move || {
+                    let program = prover.programs.latest();
+                    let proof = prover
+                        .client
+                        .prove(&program.pk, &stdin, SP1ProofMode::Groth16)
+                        .map_err(|e| e.to_string())?;
+                    prover
+                        .client
+                        .verify(&proof, &program.vk)
+                        .map_err(|e| e.to_string())
+                })
+                .await
+                .map_err(|e| e.to_string())
+                .flatten()
+        }
+        Err(e) => Err(e.to_string()),
+    };
+
+    match result {
+        Ok(()) => {
+            state.first_prove_succeeded.store(true, Ordering::Relaxed);
+            tracing::info!("startup self-test prove succeeded; ready");
+        }
+        Err(e) => tracing::error!("startup self-test prove failed: {e}"),
+    }
+}
+
+/// Fixed, well-formed guest input. The order is not valid; the check only needs the guest to
+/// run to completion.
+fn probe_inputs() -> OrderProofInputs {
+    let domain = OrderDomain {
+        chain_id: 0,
+        pool_address: [0u8; 20],
+        amount_repr: AmountRepr::U64,
+    };
+
+    OrderProofInputs {
+        market: MarketConditions {
+            current_price: 0,
+            block_timestamp: 0,
+            reference_price: 0,
+        },
+        merkle_root: [0u8; 32],
+        nullifier_hash: [0u8; 32],
+        domain,
+        disclosure: DisclosureLevel::MinimalNullifierOnly,
+        nullifier_bits: NullifierBits::Bits256,
+        fee_recipient: [0u8; 20],
+        cross_check: false,
+        order: OrderData {
+            domain,
+            wallet_address: [0u8; 20],
+            token_in: [0u8; 20],
+            token_out: [0u8; 20],
+            amount_in: 0,
+            min_amount_out: 0,
+            target_price: 0,
+            deadline: 0,
+            kind: OrderKind::ExactIn,
+            max_price_impact_bps: None,
+            fee_bps: 0,
+            group_id: None,
+        },
+        nullifier: [1u8; 32], // non-zero, as `validate` requires
+        balance: 0,
+        siblings: vec![[0u8; 32]],
+        indices: vec![0],
+        blinding: None,
+        metadata_hash: [0u8; 32],
+        balance_proof: None,
+        spent_proof: None,
+        balance_blinding: None,
+        price_proof: None,
+    }
+}
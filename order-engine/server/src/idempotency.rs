@@ -0,0 +1,55 @@
+//! ────────────────  Client-keyed deduplication of `/prove`  ────────────────
+//!
+//! A client retrying after a timeout resends its `Idempotency-Key` header. The first request
+//! with a key proves; repeats within the TTL wait for that prove if it is still running and get
+//! its response instead of proving again, without holding a runtime worker while they wait.
+//! Failed proves are never stored, so a retry with the same key proves afresh. Unlike a cache
+//! keyed by the inputs, the client decides what counts as a repeat: a reused key returns the
+//! original response even if the body changed.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use tokio::sync::OnceCell;
+
+type Slot<T> = Arc<OnceCell<T>>;
+type Entries<T> = HashMap<String, (Instant, Slot<T>)>; // key → (expiry, response once proven)
+
+pub struct IdempotencyStore<T> {
+    entries: Mutex<Entries<T>>,
+    ttl: Duration,
+}
+
+impl<T: Clone> IdempotencyStore<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    /// Returns the response stored for `key`, awaiting `f` to produce it when there is none.
+    /// Callers sharing a key while `f` runs wait for it and share its response; if it fails,
+    /// the next of them runs its own `f` instead.
+    pub async fn run<E>(&self, key: &str, f: impl Future<Output = Result<T, E>>) -> Result<T, E> {
+        let slot = {
+            let mut entries = self.entries();
+            let now = Instant::now();
+            entries.retain(|_, (expiry, _)| *expiry > now);
+            entries
+                .entry(key.to_string())
+                .or_insert_with(|| (now + self.ttl, Arc::default()))
+                .1
+                .clone()
+        };
+
+        slot.get_or_try_init(|| f).await.cloned()
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Entries<T>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
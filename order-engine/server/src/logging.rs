@@ -0,0 +1,67 @@
+//! ────────────────  Log output (`LOG_FORMAT`, `RUST_LOG`)  ────────────────
+
+use std::{fmt, str::FromStr};
+
+use anyhow::anyhow;
+use tracing_subscriber::EnvFilter;
+
+/// Filter used when `RUST_LOG` is unset: the server's own events at `info`, everything else
+/// (including SP1's prover spans) only at `warn`.
+const DEFAULT_FILTER: &str = "warn,server=info";
+
+/// Dependencies too chatty to log even when `RUST_LOG` enables everything (as in SP1's logger).
+const SILENCED: [&str; 5] = [
+    "hyper=off",
+    "p3_keccak_air=off",
+    "p3_fri=off",
+    "p3_dft=off",
+    "p3_challenger=off",
+];
+
+/// How log events are written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines.
+    Pretty,
+    /// One JSON object per event, for log pipelines.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(anyhow!(
+                "invalid LOG_FORMAT '{other}', expected \"pretty\" or \"json\""
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LogFormat::Pretty => "pretty",
+            LogFormat::Json => "json",
+        })
+    }
+}
+
+/// Installs the global subscriber; levels come from `RUST_LOG`, else `DEFAULT_FILTER`.
+pub fn init(format: LogFormat) -> anyhow::Result<()> {
+    let mut filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    for directive in SILENCED {
+        filter = filter.add_directive(directive.parse()?);
+    }
+
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match format {
+        LogFormat::Pretty => builder.compact().try_init(),
+        LogFormat::Json => builder.json().flatten_event(true).try_init(),
+    }
+    .map_err(|e| anyhow!("could not install logger: {e}"))
+}
@@ -1,15 +1,58 @@
-use axum::{Json, Router, extract::State, http::StatusCode, response::IntoResponse, routing::post};
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header::RETRY_AFTER},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
 use base64::{Engine as _, engine::general_purpose};
 use hex::FromHex;
-use once_cell::sync::Lazy;
+use schemars::{JsonSchema, Schema, schema_for};
 use serde::{Deserialize, Serialize};
+use sp1_prover::components::CpuProverComponents;
 use sp1_sdk::{
-    EnvProver, ProverClient, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin, SP1VerifyingKey,
-    include_elf, utils,
+    ExecutionReport, Prover, ProverClient, SP1ProofMode, SP1ProofWithPublicValues, SP1ProvingKey,
+    SP1PublicValues, SP1Stdin, SP1VerifyingKey, include_elf,
 };
+use tower_http::compression::CompressionLayer;
 extern crate std;
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+mod auth;
+mod commitments;
+mod config;
+mod health;
+mod idempotency;
+mod logging;
+mod metrics;
+mod nullifiers;
+mod programs;
+mod queue;
+mod request_id;
+mod request_log;
+mod requests;
+mod rpc;
+
+use commitments::CommitmentStore;
+use config::{Config, ProverBackend};
+use idempotency::IdempotencyStore;
+use metrics::Metrics;
+use nullifiers::NullifierSet;
+use programs::{Program, ProgramRegistry};
+use queue::ProveQueue;
+use request_log::RequestLog;
+use requests::RequestStore;
 
 /// ──────────────────────────────────────────────────────────────
 ///  ⚙️  SP1 guest ELF compiled from your nullifier validation program
@@ -19,26 +62,181 @@ pub const ELF: &[u8] = include_elf!("fibonacci-program");
 
 /// ────────────────  Types that already live in your guest crate  ────────────────
 /// Bring them in so we can build identical Rust structs on the host.
-use fibonacci_lib::{MarketConditions, OrderData};
+use fibonacci_lib::{
+    AmountRepr, BalanceProof, DisclosureLevel, GuestOutputs, MarketConditions, NeighborProof,
+    NonMembershipProof, NullifierBits, OrderData, OrderDomain, OrderKind, OrderProofInputs,
+    PROTOCOL_VERSION, PriceProof, TokenAddress, TokenRegistry, build_stdin, cancel_nullifier_hash,
+    compute_nullifier_hash_truncated, format_units, is_fresh,
+};
+
+/// A stored order and the program it was proven with, so a reprice proves against the same vkey
+type StoredRequest = (Arc<Program>, OrderProofInputs);
 
 /// ────────────────  Shared app-level state  ────────────────
 #[derive(Clone)]
 struct AppState {
-    client: Arc<EnvProver>,
-    pk: Arc<SP1ProvingKey>,
-    vk: Arc<SP1VerifyingKey>,
+    client: Arc<dyn Prover<CpuProverComponents>>,
+    backend: ProverBackend,
+    programs: Arc<ProgramRegistry>,
+    commitments: Arc<Mutex<CommitmentStore>>,
+    commitment_snapshot: Option<PathBuf>,
+    requests: Arc<Mutex<RequestStore<StoredRequest>>>,
+    cancelled: Arc<Mutex<NullifierSet>>, // nullifier hashes published by `/cancel`
+    nullifier_snapshot: Option<PathBuf>,
+    nullifier_bits: NullifierBits,
+    fee_recipient: [u8; 20],
+    tokens: Arc<TokenRegistry>,
+    idempotency: Arc<IdempotencyStore<ProveResponse>>,
+    request_log: Option<Arc<RequestLog>>,
+    prove_queue: Arc<ProveQueue>,
+    freshness_window_secs: u64,
+    deadline_buffer_secs: u64,
+    verify_max_age_secs: u64,
+    verify_strict_age: bool,
+    api_token: Option<String>,
+    rpc_max_batch: usize,
+    max_batch_size: usize,
+    deep_health: Arc<tokio::sync::Mutex<health::DeepCheckCache>>,
+    first_prove_succeeded: Arc<AtomicBool>, // readiness: set by the first verified proof
+    metrics: Arc<Metrics>,
 }
 
-static STATE: Lazy<AppState> = Lazy::new(|| {
-    utils::setup_logger();
-    let client = Arc::new(ProverClient::from_env());
-    let (pk, vk) = client.setup(ELF);
-    AppState {
-        client,
-        pk: Arc::new(pk),
-        vk: Arc::new(vk),
+impl AppState {
+    /// Builds the prover selected by `config` and runs key setup for the guest ELF and any
+    /// programs in `PROGRAM_DIR`.
+    fn new(config: &Config) -> anyhow::Result<Self> {
+        let client: Arc<dyn Prover<CpuProverComponents>> = match config.prover_backend {
+            ProverBackend::Local => Arc::new(ProverClient::builder().cpu().build()),
+            ProverBackend::Network => {
+                let mut builder = ProverClient::builder().network();
+                if let Some(key) = &config.network_private_key {
+                    builder = builder.private_key(key);
+                }
+                Arc::new(builder.build())
+            }
+            #[cfg(feature = "test-mock-prover")]
+            ProverBackend::Mock => Arc::new(ProverClient::builder().mock().build()),
+        };
+        tracing::info!("prover backend: {}", config.prover_backend);
+
+        let programs = ProgramRegistry::load(
+            client.as_ref(),
+            ELF,
+            PROTOCOL_VERSION,
+            config.program_dir.as_deref(),
+        )?;
+        tracing::info!(
+            "program vkey hash: {} ({} programs loaded)",
+            programs.latest().info.vkey_hash,
+            programs.len()
+        );
+
+        let request_log = match &config.request_log_dir {
+            Some(dir) => Some(Arc::new(
+                RequestLog::new(
+                    dir,
+                    config.request_log_max_files,
+                    config
+                        .request_log_key
+                        .as_ref()
+                        .filter(|key| key.len() >= 16)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "REQUEST_LOG_KEY of at least 16 bytes is required with REQUEST_LOG_DIR"
+                            )
+                        })?
+                        .as_bytes()
+                        .to_vec(),
+                )
+                .map_err(|e| anyhow::anyhow!("cannot create REQUEST_LOG_DIR {dir}: {e}"))?,
+            )),
+            None => None,
+        };
+
+        let tokens = match &config.token_config {
+            Some(path) => config::load_token_registry(path)?,
+            None => TokenRegistry::new(),
+        };
+        if !tokens.is_empty() {
+            tracing::info!("token registry: {} tokens", tokens.len());
+        }
+
+        let cancelled = match &config.nullifier_snapshot {
+            Some(path) if path.exists() => {
+                let set = NullifierSet::load_from_file(path).map_err(|e| {
+                    anyhow::anyhow!("cannot load NULLIFIER_SNAPSHOT {}: {e}", path.display())
+                })?;
+                tracing::info!("loaded {} cancelled nullifiers", set.len());
+                set
+            }
+            _ => NullifierSet::new(),
+        };
+
+        let mut commitments =
+            CommitmentStore::new(config.root_history_size, config.proof_cache_size);
+        if let Some(path) = &config.commitment_snapshot
+            && path.exists()
+        {
+            let leaves = CommitmentStore::load_leaves(path).map_err(|e| {
+                anyhow::anyhow!("cannot load COMMITMENT_SNAPSHOT {}: {e}", path.display())
+            })?;
+            commitments
+                .rebuild(&leaves)
+                .map_err(|e| anyhow::anyhow!("cannot rebuild commitment tree: {e}"))?;
+            tracing::info!("loaded {} commitments", leaves.len());
+        }
+
+        Ok(AppState {
+            client,
+            backend: config.prover_backend,
+            programs: Arc::new(programs),
+            commitments: Arc::new(Mutex::new(commitments)),
+            commitment_snapshot: config.commitment_snapshot.clone(),
+            requests: Arc::new(Mutex::new(RequestStore::new(Duration::from_secs(
+                config.request_ttl_secs,
+            )))),
+            cancelled: Arc::new(Mutex::new(cancelled)),
+            nullifier_snapshot: config.nullifier_snapshot.clone(),
+            nullifier_bits: config.nullifier_bits,
+            fee_recipient: config.fee_recipient,
+            tokens: Arc::new(tokens),
+            idempotency: Arc::new(IdempotencyStore::new(Duration::from_secs(
+                config.idempotency_ttl_secs,
+            ))),
+            request_log,
+            prove_queue: Arc::new(ProveQueue::new(config.prove_slots)),
+            freshness_window_secs: config.freshness_window_secs,
+            deadline_buffer_secs: config.deadline_buffer_secs,
+            verify_max_age_secs: config.verify_max_age_secs,
+            verify_strict_age: config.verify_strict_age,
+            api_token: config.api_token.clone(),
+            rpc_max_batch: config.rpc_max_batch,
+            max_batch_size: config.max_batch_size,
+            deep_health: Arc::new(tokio::sync::Mutex::new(None)),
+            first_prove_succeeded: Arc::new(AtomicBool::new(false)),
+            metrics: Arc::new(Metrics::new()),
+        })
     }
-});
+
+    /// Logs the metrics summary and saves the cancelled nullifiers once the server has stopped
+    /// serving. The proof cache is not saved: its entries are keyed by roots of the in-memory
+    /// commitment tree, which a restart starts empty or rebuilds with a fresh root history.
+    fn shutdown(&self) {
+        let cache_stats = self
+            .commitments
+            .lock()
+            .map(|store| store.cache_stats())
+            .unwrap_or_default();
+        self.metrics.log_summary(cache_stats);
+
+        if let Some(path) = &self.nullifier_snapshot
+            && let Ok(cancelled) = self.cancelled.lock()
+            && let Err(e) = cancelled.save_to_file(path)
+        {
+            tracing::error!("cannot save nullifier snapshot {}: {e}", path.display());
+        }
+    }
+}
 
 /// ────────────────  Helper: decode 0x… hex into fixed array  ────────────────
 fn hex_to_array<const N: usize>(s: &str) -> anyhow::Result<[u8; N]> {
@@ -51,155 +249,1201 @@ fn hex_to_array<const N: usize>(s: &str) -> anyhow::Result<[u8; N]> {
         .map_err(|_| anyhow::anyhow!("Expected {} bytes, got {}", N, len))
 }
 
+/// 0x-optional hex of 32 and 20 bytes, as accepted by `hex_to_array`
+const HEX32_PATTERN: &str = "^(0x)?[0-9a-fA-F]{64}$";
+const HEX20_PATTERN: &str = "^(0x)?[0-9a-fA-F]{40}$";
+
 /// ────────────────  Incoming payload  ────────────────
-#[derive(Deserialize)]
+// Serialize + Clone so `/prove` requests can be written to the request log and replayed.
+// The derived JSON Schema is served at `GET /schema/prove`; field docs become its descriptions.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(description = "Body of POST /prove (and of execute/prove calls to POST /rpc)")]
 struct ProveRequest {
     // Public
     market: MarketJson,
-    tree_root: String,      // 32-byte hex
-    nullifier_hash: String, // 32-byte hex
+    /// 32-byte hex
+    #[schemars(regex(pattern = HEX32_PATTERN))]
+    tree_root: String,
+    /// 32-byte hex, truncated to the server's `NULLIFIER_BITS` (high bytes zero in 128-bit mode)
+    #[schemars(regex(pattern = HEX32_PATTERN))]
+    nullifier_hash: String,
+    domain: DomainJson,
+    /// "Full" (default) or "MinimalNullifierOnly"
+    #[serde(default)]
+    disclosure: DisclosureLevel,
+    /// Re-derive the statement a second way in the guest and report `cross_check_ok`; doubles
+    /// the order checks, so meant for execute calls (default false)
+    #[serde(default)]
+    cross_check: bool,
     // Private
     order: OrderJson,
-    commitment_nullifier: String, // 32-byte hex
+    /// 32-byte hex
+    #[schemars(regex(pattern = HEX32_PATTERN))]
+    commitment_nullifier: String,
     balance: u64,
-    siblings: Vec<String>, // Vec<32-byte hex>
+    /// 32-byte hex per Merkle level, leaf first
+    #[schemars(inner(regex(pattern = HEX32_PATTERN)))]
+    siblings: Vec<String>,
+    /// 0 (left) or 1 (right) per Merkle level
+    #[schemars(inner(range(max = 1)))]
     indices: Vec<u8>,
+    /// 32-byte hex, for hiding leaves
+    #[serde(default)]
+    #[schemars(regex(pattern = HEX32_PATTERN))]
+    blinding: Option<String>,
+    /// 32-byte hex, audit metadata bound into the commitment
+    #[serde(default)]
+    #[schemars(regex(pattern = HEX32_PATTERN))]
+    metadata_hash: Option<String>,
+    /// Balance tree path, for commitments that do not bind the balance
+    #[serde(default)]
+    balance_proof: Option<BalanceProofJson>,
+    /// Neighbors proving the nullifier is not in the spent-nullifier tree, checked in the guest
+    #[serde(default)]
+    spent_proof: Option<SpentProofJson>,
+    /// 32-byte hex, for commitments binding a blinded balance instead of the raw one
+    #[serde(default)]
+    #[schemars(regex(pattern = HEX32_PATTERN))]
+    balance_blinding: Option<String>,
+    /// Oracle price tree path for the order's token pair, checked against `market.current_price`
+    #[serde(default)]
+    price_proof: Option<PriceProofJson>,
+    /// Relayer priority, not part of the order: higher tips get a proving slot first
+    #[serde(default)]
+    tip: u64,
+    /// Token decimals for the response's `*_decimal` amounts; display only, never proven
+    #[serde(default)]
+    decimals: Option<TokenDecimals>,
+    /// 32-byte hex vkey hash of the program to run; the latest (`GET /program`) when omitted
+    #[serde(default)]
+    #[schemars(regex(pattern = HEX32_PATTERN))]
+    vkey_hash: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Copy, Serialize, Deserialize, JsonSchema)]
+struct TokenDecimals {
+    token_in: u8,
+    token_out: u8,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+struct BalanceProofJson {
+    /// 32-byte hex balance tree root
+    #[schemars(regex(pattern = HEX32_PATTERN))]
+    root: String,
+    /// 32-byte hex per level, leaf first
+    #[schemars(inner(regex(pattern = HEX32_PATTERN)))]
+    siblings: Vec<String>,
+    /// 0 (left) or 1 (right) per level
+    #[schemars(inner(range(max = 1)))]
+    indices: Vec<u8>,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+struct PriceProofJson {
+    /// 32-byte hex oracle price tree root
+    #[schemars(regex(pattern = HEX32_PATTERN))]
+    root: String,
+    /// 32-byte hex per level, leaf first
+    #[schemars(inner(regex(pattern = HEX32_PATTERN)))]
+    siblings: Vec<String>,
+    /// 0 (left) or 1 (right) per level
+    #[schemars(inner(range(max = 1)))]
+    indices: Vec<u8>,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+struct SpentProofJson {
+    /// 32-byte hex spent-nullifier tree root
+    #[schemars(regex(pattern = HEX32_PATTERN))]
+    root: String,
+    low: NeighborProofJson,
+    high: NeighborProofJson,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+struct NeighborProofJson {
+    /// 32-byte hex leaf
+    #[schemars(regex(pattern = HEX32_PATTERN))]
+    leaf: String,
+    /// 32-byte hex per level, leaf first
+    #[schemars(inner(regex(pattern = HEX32_PATTERN)))]
+    siblings: Vec<String>,
+    /// 0 (left) or 1 (right) per level
+    #[schemars(inner(range(max = 1)))]
+    indices: Vec<u8>,
+}
+
+impl NeighborProofJson {
+    fn to_proof(&self) -> anyhow::Result<NeighborProof> {
+        Ok(NeighborProof {
+            leaf: hex_to_array::<32>(&self.leaf)?,
+            siblings: self
+                .siblings
+                .iter()
+                .map(|h| hex_to_array::<32>(h))
+                .collect::<Result<_, _>>()?,
+            indices: self.indices.clone(),
+        })
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
 struct MarketJson {
     current_price: u64,
     block_timestamp: u64,
+    /// Oracle reference for orders with `max_price_impact_bps`; 0 (default) when unavailable
+    #[serde(default)]
+    reference_price: u64,
+    /// Prices are quoted at token_in's `TOKEN_CONFIG` scale and normalized by the server, instead
+    /// of raw `PRICE_SCALE` units (default false)
+    #[serde(default)]
+    quoted: bool,
 }
 
-#[derive(Deserialize)]
+impl ProveRequest {
+    /// Copy safe to write to `log`. The wallet address is replaced by the first 20 bytes of its
+    /// keyed hash, so logs don't link orders to wallets. Every private witness that opens the
+    /// commitment or locates its leaf is zeroed: the commitment nullifier, balance, blinders,
+    /// metadata hash and the commitment and balance tree paths. Which witnesses were present
+    /// and the path lengths are kept for debugging.
+    fn sanitized(&self, log: &RequestLog) -> Self {
+        fn redact(hex32: &mut String) {
+            *hex32 = format!("0x{}", hex::encode([0u8; 32]));
+        }
+        fn redact_path(siblings: &mut [String], indices: &mut [u8]) {
+            siblings.iter_mut().for_each(redact);
+            indices.fill(0);
+        }
+
+        let wallet = match hex_to_array::<20>(&self.order.wallet_address) {
+            Ok(wallet) => log.keyed_hash(&wallet),
+            Err(_) => log.keyed_hash(self.order.wallet_address.as_bytes()),
+        };
+
+        let mut sanitized = self.clone();
+        sanitized.order.wallet_address = format!("0x{}", hex::encode(&wallet[..20]));
+        redact(&mut sanitized.commitment_nullifier);
+        sanitized.balance = 0;
+        redact_path(&mut sanitized.siblings, &mut sanitized.indices);
+        sanitized.blinding.iter_mut().for_each(redact);
+        sanitized.metadata_hash.iter_mut().for_each(redact);
+        sanitized.balance_blinding.iter_mut().for_each(redact);
+        if let Some(proof) = &mut sanitized.balance_proof {
+            redact_path(&mut proof.siblings, &mut proof.indices);
+        }
+        sanitized
+    }
+}
+
+impl MarketJson {
+    fn to_market(&self) -> MarketConditions {
+        MarketConditions {
+            current_price: self.current_price,
+            block_timestamp: self.block_timestamp,
+            reference_price: self.reference_price,
+        }
+    }
+
+    /// `to_market`, normalizing quoted prices through `tokens` for the order's pair
+    fn resolve(
+        &self,
+        tokens: &TokenRegistry,
+        order: &OrderData,
+    ) -> Result<MarketConditions, (StatusCode, String)> {
+        if !self.quoted {
+            return Ok(self.to_market());
+        }
+        tokens
+            .market(
+                &TokenAddress(order.token_in),
+                &TokenAddress(order.token_out),
+                self.current_price,
+                self.reference_price,
+                self.block_timestamp,
+            )
+            .map_err(to_400)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+struct DomainJson {
+    chain_id: u64,
+    /// 20-byte hex
+    #[schemars(regex(pattern = HEX20_PATTERN))]
+    pool_address: String,
+    /// "U64" (default) or "U32", the width the deployment hashes order amounts at
+    #[serde(default)]
+    amount_repr: AmountRepr,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
 struct OrderJson {
-    wallet_address: String, // 20-byte hex
-    token_in: String,       // 20-byte hex
-    token_out: String,      // 20-byte hex
+    /// 20-byte hex
+    #[schemars(regex(pattern = HEX20_PATTERN))]
+    wallet_address: String,
+    /// 20-byte hex
+    #[schemars(regex(pattern = HEX20_PATTERN))]
+    token_in: String,
+    /// 20-byte hex
+    #[schemars(regex(pattern = HEX20_PATTERN))]
+    token_out: String,
     amount_in: u64,
     min_amount_out: u64,
     target_price: u64,
     deadline: u64,
+    /// "ExactIn" (default) or {"ExactOut": {"exact_amount_out", "max_amount_in"}}
+    #[serde(default)]
+    kind: OrderKind,
+    /// Maximum deviation of the market price from `market.reference_price`, in basis points
+    #[serde(default)]
+    max_price_impact_bps: Option<u32>,
+    /// Protocol fee taken from the gross output, in basis points (at most 10000)
+    #[serde(default)]
+    #[schemars(range(max = 10_000))]
+    fee_bps: u16,
+    /// 32-byte hex; orders sharing a group settle all-or-nothing
+    #[serde(default)]
+    #[schemars(regex(pattern = HEX32_PATTERN))]
+    group_id: Option<String>,
 }
 
 /// ────────────────  Outgoing response  ────────────────
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 struct ProveResponse {
+    request_id: String, // pass to /prove/reprice while it is retained
+    tip: u64,
+    cycles: u64,
+    cycle_breakdown: BTreeMap<String, u64>, // cycles per guest region (hashing, merkle, ...)
+    #[serde(flatten)]
+    outputs: OutputsJson,
+    seconds_to_deadline: i64, // order deadline minus the proven market timestamp
+    // proof
+    proof_b64: String,
+    verified: bool,
+
+    vkey: Arc<SP1VerifyingKey>,
+    pk: Arc<SP1ProvingKey>,
+}
+
+#[derive(Serialize)]
+struct ExecuteResponse {
     cycles: u64,
-    // echoed guest outputs
+    cycle_breakdown: BTreeMap<String, u64>,
+    #[serde(flatten)]
+    outputs: OutputsJson,
+}
+
+/// Echoed `GuestOutputs`
+#[derive(Clone, Serialize)]
+struct OutputsJson {
     valid: bool,
     nullifier_hash: String,
+    nullifier_bits: u16,
+    merkle_root: String,
+    chain_id: u64,
+    pool_address: String,
+    block_timestamp: u64,
+    metadata_present: bool,
+    // only when the balance was proven against a balance tree
+    #[serde(skip_serializing_if = "Option::is_none")]
+    balance_root: Option<String>,
+    // only when the guest checked the spent-nullifier set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spent_root: Option<String>,
+    // only when the guest checked the market price against an oracle price tree
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price_root: Option<String>,
+    // only when the request set cross_check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cross_check_ok: Option<bool>,
+    // only disclosed at DisclosureLevel::Full
+    #[serde(skip_serializing_if = "Option::is_none")]
+    settlement: Option<SettlementOutputs>,
+}
+
+#[derive(Clone, Serialize)]
+struct SettlementOutputs {
     wallet_address: String,
     amount_in: u64,
     min_amount_out: u64,
-    // proof
+    fee_amount: u64,
+    fee_recipient: String,
+    // amounts in whole tokens, when the request supplied decimals
+    #[serde(skip_serializing_if = "Option::is_none")]
+    amount_in_decimal: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_amount_out_decimal: Option<String>,
+}
+
+impl OutputsJson {
+    /// Fills in the settlement's `*_decimal` display amounts from client-supplied decimals.
+    fn add_decimals(&mut self, decimals: Option<TokenDecimals>) {
+        if let (Some(settlement), Some(decimals)) = (&mut self.settlement, decimals) {
+            settlement.amount_in_decimal =
+                Some(format_units(settlement.amount_in, decimals.token_in));
+            settlement.min_amount_out_decimal =
+                Some(format_units(settlement.min_amount_out, decimals.token_out));
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RepriceRequest {
+    request_id: String,
+    market: MarketJson,
+    #[serde(default)]
+    tip: u64,
+    #[serde(default)]
+    decimals: Option<TokenDecimals>,
+}
+
+#[derive(Deserialize)]
+struct DecodeRequest {
     proof_b64: String,
-    verified: bool,
+}
 
-    vkey: Arc<SP1VerifyingKey>,
-    pk: Arc<SP1ProvingKey>,
+/// Identifies the order to cancel by its private nullifier, either derived from `user_secret`
+/// and `order_context` as in `generate_order_nullifier` or given directly as `nullifier`.
+#[derive(Deserialize)]
+struct CancelRequest {
+    #[serde(default)]
+    user_secret: Option<String>, // 32-byte hex
+    #[serde(default)]
+    order_context: Option<String>, // 32-byte hex
+    #[serde(default)]
+    nullifier: Option<String>, // 32-byte hex
+}
+
+#[derive(Serialize)]
+struct CancelResponse {
+    /// Nullifier hash for settlement to mark spent
+    nullifier_hash: String,
+}
+
+#[derive(Deserialize)]
+struct CommitmentRequest {
+    commitment_hash: String, // 32-byte hex
+}
+
+#[derive(Serialize)]
+struct RebuildTreeResponse {
+    root: String,
+    leaf_count: usize,
+}
+
+#[derive(Serialize)]
+struct InclusionProofResponse {
+    root: String,
+    leaf_count: usize,
+    siblings: Vec<String>,
+    indices: Vec<u8>,
+    cached: bool,
+}
+
+/// Identity of the loaded guest program, for clients pinning a known-good version.
+#[derive(Clone, Serialize)]
+struct ProgramResponse {
+    vkey_hash: String,
+    elf_sha256: String,
+    protocol_version: u32,
+}
+
+/// `/program`: the latest program and the request limits clients size batches by
+#[derive(Serialize)]
+struct ProgramInfoResponse {
+    #[serde(flatten)]
+    program: ProgramResponse,
+    max_batch_size: usize,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn to_400<E: std::fmt::Display>(err: E) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, err.to_string())
 }
 
 fn to_500<E: std::fmt::Display>(err: E) -> (StatusCode, String) {
     (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
 }
 
-/// ────────────────  Route handler  ────────────────
-async fn prove_handler(
-    State(state): State<AppState>,
-    Json(req): Json<ProveRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    // ─── Convert JSON → Rust structs expected by guest ───
-    let market = MarketConditions {
-        current_price: req.market.current_price,
-        block_timestamp: req.market.block_timestamp,
-    };
+/// Seconds a 503 tells clients to wait (`Retry-After`) before retrying
+const PROVER_RETRY_AFTER_SECS: u64 = 30;
 
-    let tree_root = hex_to_array::<32>(&req.tree_root).map_err(to_500)?;
-    let nullifier_hash_arr = hex_to_array::<32>(&req.nullifier_hash).map_err(to_500)?;
+/// Error of the proving paths. Only `ProverUnavailable` answers 503 with `Retry-After`; every
+/// other failure keeps its status and message.
+enum ProveError {
+    Status(StatusCode, String),
+    /// The network prover could not be reached
+    ProverUnavailable(String),
+}
+
+impl ProveError {
+    fn message(&self) -> &str {
+        match self {
+            Self::Status(_, message) | Self::ProverUnavailable(message) => message,
+        }
+    }
+}
+
+impl From<(StatusCode, String)> for ProveError {
+    fn from((status, message): (StatusCode, String)) -> Self {
+        Self::Status(status, message)
+    }
+}
+
+/// For `/rpc`, whose per-call errors carry the status code but no headers
+impl From<ProveError> for (StatusCode, String) {
+    fn from(err: ProveError) -> Self {
+        match err {
+            ProveError::Status(status, message) => (status, message),
+            ProveError::ProverUnavailable(message) => (StatusCode::SERVICE_UNAVAILABLE, message),
+        }
+    }
+}
+
+impl IntoResponse for ProveError {
+    fn into_response(self) -> Response {
+        let retry_after = matches!(self, Self::ProverUnavailable(_));
+        let mut response = <(StatusCode, String)>::from(self).into_response();
+        if retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(PROVER_RETRY_AFTER_SECS));
+        }
+        response
+    }
+}
+
+/// Maps a failed prove to `ProverUnavailable` when the network prover could not be reached, so
+/// clients can tell "prover temporarily down" from a bad request; anything else stays a 500.
+fn prove_error(backend: ProverBackend, err: anyhow::Error) -> ProveError {
+    if backend == ProverBackend::Network && backend_unavailable(&err) {
+        return ProveError::ProverUnavailable(format!(
+            "prover backend unavailable, retry in {PROVER_RETRY_AFTER_SECS}s (executing without \
+             proving still works): {err:#}"
+        ));
+    }
+    to_500(err).into()
+}
+
+/// Whether `err` reports the prover network as unreachable rather than rejecting the request:
+/// a gRPC `Unavailable` or `DeadlineExceeded` status, a request the network timed out, or a
+/// transport, HTTP connect or socket failure anywhere in its source chain.
+fn backend_unavailable(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if let Some(status) = cause.downcast_ref::<tonic::Status>() {
+            return matches!(
+                status.code(),
+                tonic::Code::Unavailable | tonic::Code::DeadlineExceeded
+            );
+        }
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            return err.is_connect() || err.is_timeout();
+        }
+        if let Some(err) = cause.downcast_ref::<std::io::Error>() {
+            return matches!(
+                err.kind(),
+                std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::NotConnected
+                    | std::io::ErrorKind::TimedOut
+            );
+        }
+        matches!(
+            cause.downcast_ref::<sp1_sdk::network::Error>(),
+            Some(sp1_sdk::network::Error::RequestTimedOut { .. })
+        ) || cause.is::<tonic::transport::Error>()
+    })
+}
+
+/// ────────────────  Decoded guest inputs  ────────────────
+/// Decoded once per `/prove` and retained for `/prove/reprice`, which swaps in new market
+/// conditions and proves again.
+fn inputs_from_request(
+    req: &ProveRequest,
+    nullifier_bits: NullifierBits,
+    fee_recipient: [u8; 20],
+) -> anyhow::Result<OrderProofInputs> {
+    let domain = OrderDomain {
+        chain_id: req.domain.chain_id,
+        pool_address: hex_to_array::<20>(&req.domain.pool_address)?,
+        amount_repr: req.domain.amount_repr,
+    };
 
     let order = OrderData {
-        wallet_address: hex_to_array::<20>(&req.order.wallet_address).map_err(to_500)?,
-        token_in: hex_to_array::<20>(&req.order.token_in).map_err(to_500)?,
-        token_out: hex_to_array::<20>(&req.order.token_out).map_err(to_500)?,
+        domain,
+        wallet_address: hex_to_array::<20>(&req.order.wallet_address)?,
+        token_in: hex_to_array::<20>(&req.order.token_in)?,
+        token_out: hex_to_array::<20>(&req.order.token_out)?,
         amount_in: req.order.amount_in,
         min_amount_out: req.order.min_amount_out,
         target_price: req.order.target_price,
         deadline: req.order.deadline,
+        kind: req.order.kind,
+        max_price_impact_bps: req.order.max_price_impact_bps,
+        fee_bps: req.order.fee_bps,
+        group_id: req
+            .order
+            .group_id
+            .as_deref()
+            .map(hex_to_array::<32>)
+            .transpose()?,
     };
 
-    let commitment_nullifier = hex_to_array::<32>(&req.commitment_nullifier).map_err(to_500)?;
+    Ok(OrderProofInputs {
+        market: req.market.to_market(),
+        merkle_root: hex_to_array::<32>(&req.tree_root)?,
+        nullifier_hash: hex_to_array::<32>(&req.nullifier_hash)?,
+        domain,
+        disclosure: req.disclosure,
+        nullifier_bits,
+        fee_recipient,
+        cross_check: req.cross_check,
+        order,
+        nullifier: hex_to_array::<32>(&req.commitment_nullifier)?,
+        balance: req.balance,
+        siblings: req
+            .siblings
+            .iter()
+            .map(|h| hex_to_array::<32>(h))
+            .collect::<Result<_, _>>()?,
+        indices: req.indices.clone(),
+        blinding: req
+            .blinding
+            .as_deref()
+            .map(hex_to_array::<32>)
+            .transpose()?,
+        metadata_hash: req
+            .metadata_hash
+            .as_deref()
+            .map(hex_to_array::<32>)
+            .transpose()?
+            .unwrap_or([0u8; 32]),
+        balance_proof: req
+            .balance_proof
+            .as_ref()
+            .map(|proof| -> anyhow::Result<_> {
+                Ok(BalanceProof {
+                    root: hex_to_array::<32>(&proof.root)?,
+                    siblings: proof
+                        .siblings
+                        .iter()
+                        .map(|h| hex_to_array::<32>(h))
+                        .collect::<Result<_, _>>()?,
+                    indices: proof.indices.clone(),
+                })
+            })
+            .transpose()?,
+        spent_proof: req
+            .spent_proof
+            .as_ref()
+            .map(|proof| -> anyhow::Result<_> {
+                Ok(NonMembershipProof {
+                    root: hex_to_array::<32>(&proof.root)?,
+                    low: proof.low.to_proof()?,
+                    high: proof.high.to_proof()?,
+                })
+            })
+            .transpose()?,
+        balance_blinding: req
+            .balance_blinding
+            .as_deref()
+            .map(hex_to_array::<32>)
+            .transpose()?,
+        price_proof: req
+            .price_proof
+            .as_ref()
+            .map(|proof| -> anyhow::Result<_> {
+                Ok(PriceProof {
+                    root: hex_to_array::<32>(&proof.root)?,
+                    siblings: proof
+                        .siblings
+                        .iter()
+                        .map(|h| hex_to_array::<32>(h))
+                        .collect::<Result<_, _>>()?,
+                    indices: proof.indices.clone(),
+                })
+            })
+            .transpose()?,
+    })
+}
+
+/// Rejects stale market data before spending any proving time.
+fn check_fresh(state: &AppState, market: &MarketConditions) -> Result<(), (StatusCode, String)> {
+    if state.freshness_window_secs > 0
+        && !is_fresh(
+            market.block_timestamp,
+            unix_now(),
+            state.freshness_window_secs,
+        )
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "market.block_timestamp {} is older than the {}s freshness window",
+                market.block_timestamp, state.freshness_window_secs
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Rejects orders that would expire before a proof against `market` could be settled.
+fn check_deadline_buffer(
+    state: &AppState,
+    order: &OrderData,
+    market: &MarketConditions,
+) -> Result<(), (StatusCode, String)> {
+    if order.deadline
+        < market
+            .block_timestamp
+            .saturating_add(state.deadline_buffer_secs)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "order.deadline {} is less than {}s after market.block_timestamp {}; the order would expire before proving completes",
+                order.deadline, state.deadline_buffer_secs, market.block_timestamp
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Reads the guest-committed `GuestOutputs`; settlement fields only exist at
+/// `DisclosureLevel::Full`.
+fn read_outputs(public_values: &mut SP1PublicValues) -> OutputsJson {
+    public_values.read::<GuestOutputs>().into()
+}
 
-    let siblings: Vec<[u8; 32]> = req
-        .siblings
+/// Decodes a `proof_b64` as returned by `/prove` back into the proof.
+fn decode_proof_b64(proof_b64: &str) -> Result<SP1ProofWithPublicValues, (StatusCode, String)> {
+    let bytes = general_purpose::URL_SAFE_NO_PAD
+        .decode(proof_b64)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid proof_b64: {e}")))?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid proof: {e}")))
+}
+
+impl From<GuestOutputs> for OutputsJson {
+    fn from(outputs: GuestOutputs) -> Self {
+        let settlement = outputs.settlement.map(|fields| SettlementOutputs {
+            wallet_address: format!("0x{}", hex::encode(fields.wallet_address)),
+            amount_in: fields.amount_in,
+            min_amount_out: fields.min_amount_out,
+            fee_amount: fields.fee_amount,
+            fee_recipient: format!("0x{}", hex::encode(fields.fee_recipient)),
+            amount_in_decimal: None,
+            min_amount_out_decimal: None,
+        });
+        OutputsJson {
+            valid: outputs.valid,
+            nullifier_hash: format!("0x{}", hex::encode(outputs.nullifier_hash)),
+            nullifier_bits: outputs.nullifier_bits.bits(),
+            merkle_root: format!("0x{}", hex::encode(outputs.merkle_root)),
+            chain_id: outputs.domain.chain_id,
+            pool_address: format!("0x{}", hex::encode(outputs.domain.pool_address)),
+            block_timestamp: outputs.block_timestamp,
+            metadata_present: outputs.metadata_present,
+            balance_root: outputs
+                .balance_root
+                .map(|root| format!("0x{}", hex::encode(root))),
+            spent_root: outputs
+                .spent_root
+                .map(|root| format!("0x{}", hex::encode(root))),
+            price_root: outputs
+                .price_root
+                .map(|root| format!("0x{}", hex::encode(root))),
+            cross_check_ok: outputs.cross_check_ok,
+            settlement,
+        }
+    }
+}
+
+/// Decodes a `ProveRequest` and rejects inputs that cannot yield a useful proof, before any
+/// zkVM work.
+fn checked_inputs(
+    state: &AppState,
+    req: &ProveRequest,
+) -> Result<OrderProofInputs, (StatusCode, String)> {
+    // ─── Convert JSON → Rust structs expected by guest ───
+    let mut inputs =
+        inputs_from_request(req, state.nullifier_bits, state.fee_recipient).map_err(to_500)?;
+    inputs.market = req.market.resolve(&state.tokens, &inputs.order)?;
+    inputs.validate().map_err(to_400)?;
+    check_not_cancelled(state, &inputs.nullifier_hash)?;
+    check_fresh(state, &inputs.market)?;
+    check_deadline_buffer(state, &inputs.order, &inputs.market)?;
+    Ok(inputs)
+}
+
+/// Rejects orders whose nullifier hash was published by `/cancel`.
+fn check_not_cancelled(
+    state: &AppState,
+    nullifier_hash: &[u8; 32],
+) -> Result<(), (StatusCode, String)> {
+    if state
+        .cancelled
+        .lock()
+        .map_err(to_500)?
+        .contains(nullifier_hash)
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "order cancelled: nullifier hash 0x{} is spent",
+                hex::encode(nullifier_hash)
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Cycles spent in each region the guest marks with `cycle-tracker-report` markers, sorted by name.
+fn cycle_breakdown(report: &ExecutionReport) -> BTreeMap<String, u64> {
+    report
+        .cycle_tracker
         .iter()
-        .map(|h| hex_to_array::<32>(h))
-        .collect::<Result<_, _>>()
+        .map(|(name, cycles)| (name.clone(), *cycles))
+        .collect()
+}
+
+/// Executes `inputs` on `program` without proving and echoes the guest outputs.
+fn run_execute(
+    state: &AppState,
+    program: &Program,
+    inputs: &OrderProofInputs,
+) -> Result<ExecuteResponse, (StatusCode, String)> {
+    let (mut public_values, report) = state
+        .client
+        .execute(&program.elf, &build_stdin(inputs).map_err(to_400)?)
         .map_err(to_500)?;
 
-    // ─── Build stdin exactly like in your script ───
-    let mut stdin = SP1Stdin::new();
-    // public
-    stdin.write(&market);
-    stdin.write(&tree_root);
-    stdin.write(&nullifier_hash_arr);
-    // private
-    stdin.write(&order);
-    stdin.write(&commitment_nullifier);
-    stdin.write(&req.balance);
-    stdin.write(&siblings);
-    stdin.write(&req.indices);
+    Ok(ExecuteResponse {
+        cycles: report.total_instruction_count(),
+        cycle_breakdown: cycle_breakdown(&report),
+        outputs: read_outputs(&mut public_values),
+    })
+}
+
+/// Proves a new request and retains its inputs for `/prove/reprice`.
+async fn prove_request(state: &AppState, req: &ProveRequest) -> Result<ProveResponse, ProveError> {
+    let program = state.programs.get(req.vkey_hash.as_deref())?;
+    let inputs = checked_inputs(state, req)?;
+    let request_id = state
+        .requests
+        .lock()
+        .map_err(to_500)?
+        .insert((program.clone(), inputs.clone()));
+
+    // Written before proving so the request survives a crash or failed prove
+    let log_path = state.request_log.as_ref().and_then(|log| {
+        log.write(&request_id, &req.sanitized(log))
+            .inspect_err(|e| tracing::warn!("could not log request {request_id}: {e}"))
+            .ok()
+    });
+
+    let mut response = run_prove(state, &program, &inputs, request_id.clone(), req.tip)
+        .await
+        .inspect_err(|err| {
+            if let Some(path) = &log_path {
+                tracing::error!(
+                    "prove {request_id} failed: {}; request logged at {}",
+                    err.message(),
+                    path.display()
+                );
+            }
+        })?;
+    response.outputs.add_decimals(req.decimals);
+    Ok(response)
+}
+
+/// Executes, proves and verifies `inputs` on `program`, echoing the guest outputs. The prove
+/// runs on the blocking pool once a proving slot is free.
+async fn run_prove(
+    state: &AppState,
+    program: &Arc<Program>,
+    inputs: &OrderProofInputs,
+    request_id: String,
+    tip: u64,
+) -> Result<ProveResponse, ProveError> {
+    let stdin = build_stdin(inputs).map_err(to_400)?;
+    let seconds_to_deadline = inputs
+        .order
+        .seconds_to_deadline(inputs.market.block_timestamp);
+    let (prover, program) = (state.clone(), program.clone());
+    state
+        .prove_queue
+        .run(tip, move || {
+            prove_stdin(
+                &prover,
+                &program,
+                &stdin,
+                request_id,
+                tip,
+                seconds_to_deadline,
+            )
+        })
+        .await
+        .map_err(to_500)?
+}
 
+/// Proving body of `run_prove`, run once a proving slot is free.
+fn prove_stdin(
+    state: &AppState,
+    program: &Program,
+    stdin: &SP1Stdin,
+    request_id: String,
+    tip: u64,
+    seconds_to_deadline: i64,
+) -> Result<ProveResponse, ProveError> {
     // ─── Execute for cycle count (optional) ───
-    let (_, exec_report) = state.client.execute(ELF, &stdin).run().map_err(to_500)?;
+    let (_, exec_report) = state.client.execute(&program.elf, stdin).map_err(to_500)?;
     let cycles = exec_report.total_instruction_count();
+    let cycle_breakdown = cycle_breakdown(&exec_report);
 
     // ─── Prove & verify (unchanged) ───
-    let mut proof = state
+    let started = Instant::now();
+    let proof = state
         .client
-        .prove(&state.pk, &stdin)
-        .groth16()
-        .run()
-        .map_err(to_500)?;
+        .prove(&program.pk, stdin, SP1ProofMode::Groth16);
+    state.metrics.record_proof(started.elapsed(), proof.is_ok());
+    let mut proof = proof.map_err(|e| prove_error(state.backend, e))?;
 
-    let verified = state.client.verify(&proof, &state.vk).is_ok();
+    let verified = state.client.verify(&proof, &program.vk).is_ok();
+    if verified {
+        state.first_prove_succeeded.store(true, Ordering::Relaxed);
+    }
 
     // ─── Read guest-committed outputs ───
-    let valid = proof.public_values.read::<bool>();
-    let out_nullifier = proof.public_values.read::<[u8; 32]>();
-    let out_wallet = proof.public_values.read::<[u8; 20]>();
-    let amount_in = proof.public_values.read::<u64>();
-    let min_amount_out = proof.public_values.read::<u64>();
+    let outputs = read_outputs(&mut proof.public_values);
 
     // ─── Serialize proof to b64 ───
     let proof_bytes = serde_json::to_vec(&proof).map_err(to_500)?; // Vec<u8>
     let proof_b64 = general_purpose::URL_SAFE_NO_PAD.encode(&proof_bytes);
 
-    // ─── Return JSON ───
-    Ok(Json(ProveResponse {
+    Ok(ProveResponse {
+        request_id,
+        tip,
         cycles,
-        valid,
-        nullifier_hash: format!("0x{}", hex::encode(out_nullifier)),
-        wallet_address: format!("0x{}", hex::encode(out_wallet)),
-        amount_in,
-        min_amount_out,
+        cycle_breakdown,
+        outputs,
+        seconds_to_deadline,
         proof_b64,
         verified,
-        vkey: state.vk,
-        pk: state.pk,
+        vkey: program.vk.clone(),
+        pk: program.pk.clone(),
+    })
+}
+
+/// ────────────────  Route handlers  ────────────────
+/// Proves an order. Requests carrying an `Idempotency-Key` header already seen within
+/// `IDEMPOTENCY_TTL_SECS` return that request's response (waiting for it if still proving).
+async fn prove_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<ProveRequest>,
+) -> Result<impl IntoResponse, ProveError> {
+    let response = match headers.get("idempotency-key") {
+        Some(key) => {
+            let key = key
+                .to_str()
+                .ok()
+                .filter(|key| !key.trim().is_empty())
+                .ok_or((
+                    StatusCode::BAD_REQUEST,
+                    "Idempotency-Key must be a non-empty ASCII string".to_string(),
+                ))?;
+            state
+                .idempotency
+                .run(key, prove_request(&state, &req))
+                .await?
+        }
+        None => prove_request(&state, &req).await?,
+    };
+    Ok(Json(response))
+}
+
+/// Re-proves a previously submitted order against new market conditions, reusing its stored
+/// private inputs and inclusion proof. Requires the `API_TOKEN` bearer token.
+async fn reprice_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<RepriceRequest>,
+) -> Result<impl IntoResponse, ProveError> {
+    auth::require_bearer(&headers, state.api_token.as_deref())?;
+
+    let (program, mut inputs) = state
+        .requests
+        .lock()
+        .map_err(to_500)?
+        .get(&req.request_id)
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "unknown or expired request_id".to_string(),
+        ))?;
+
+    inputs.market = req.market.resolve(&state.tokens, &inputs.order)?;
+    check_not_cancelled(&state, &inputs.nullifier_hash)?;
+    check_fresh(&state, &inputs.market)?;
+    check_deadline_buffer(&state, &inputs.order, &inputs.market)?;
+
+    let mut response =
+        run_prove(&state, &program, &inputs, req.request_id.clone(), req.tip).await?;
+    response.outputs.add_decimals(req.decimals);
+    state
+        .requests
+        .lock()
+        .map_err(to_500)?
+        .update(&req.request_id, (program, inputs));
+
+    Ok(Json(response))
+}
+
+fn inclusion_proof_response(
+    store: &mut CommitmentStore,
+    commitment_hash: [u8; 32],
+) -> Result<InclusionProofResponse, (StatusCode, String)> {
+    let ((siblings, indices), cached) = store.proof(commitment_hash).ok_or((
+        StatusCode::NOT_FOUND,
+        "Commitment not found in tree".to_string(),
+    ))?;
+
+    Ok(InclusionProofResponse {
+        root: format!("0x{}", hex::encode(store.current_root())),
+        leaf_count: store.len(),
+        siblings: siblings
+            .iter()
+            .map(|s| format!("0x{}", hex::encode(s)))
+            .collect(),
+        indices,
+        cached,
+    })
+}
+
+/// Cancels an order by recording its nullifier hash as spent, so `/prove`, `/prove/reprice` and
+/// `execute` refuse it, and returns the hash for on-chain recording. Only the owner can cancel:
+/// the request must carry the nullifier preimage (or the secret it is derived from), which a
+/// caller knowing just the public nullifier hash cannot produce.
+async fn cancel_handler(
+    State(state): State<AppState>,
+    Json(req): Json<CancelRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let nullifier_hash = match (&req.nullifier, &req.user_secret, &req.order_context) {
+        (Some(nullifier), None, None) => {
+            let nullifier = hex_to_array::<32>(nullifier).map_err(to_400)?;
+            if nullifier == [0u8; 32] {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "nullifier must be non-zero".to_string(),
+                ));
+            }
+            compute_nullifier_hash_truncated(&nullifier, state.nullifier_bits)
+        }
+        (None, Some(user_secret), Some(order_context)) => {
+            state.nullifier_bits.truncate(cancel_nullifier_hash(
+                &hex_to_array::<32>(user_secret).map_err(to_400)?,
+                &hex_to_array::<32>(order_context).map_err(to_400)?,
+            ))
+        }
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "give either nullifier or both user_secret and order_context".to_string(),
+            ));
+        }
+    };
+
+    {
+        let mut cancelled = state.cancelled.lock().map_err(to_500)?;
+        // Saved before answering, so a cancel the client saw succeed survives a restart
+        if cancelled.insert(nullifier_hash)
+            && let Some(path) = &state.nullifier_snapshot
+        {
+            cancelled.save_to_file(path).map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("cannot save nullifier snapshot: {e}"),
+                )
+            })?;
+        }
+    }
+    tracing::info!("cancelled order 0x{}", hex::encode(nullifier_hash));
+
+    Ok(Json(CancelResponse {
+        nullifier_hash: format!("0x{}", hex::encode(nullifier_hash)),
+    }))
+}
+
+/// Appends a commitment to the server tree and returns its proof against the new root.
+async fn add_commitment_handler(
+    State(state): State<AppState>,
+    Json(req): Json<CommitmentRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let commitment_hash = hex_to_array::<32>(&req.commitment_hash).map_err(to_500)?;
+
+    let mut store = state.commitments.lock().map_err(to_500)?;
+    store
+        .insert(commitment_hash)
+        .map_err(|e| (StatusCode::CONFLICT, e))?;
+    if let Some(path) = &state.commitment_snapshot {
+        store.save_to_file(path).map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("cannot save commitment snapshot: {e}"),
+            )
+        })?;
+    }
+    Ok(Json(inclusion_proof_response(&mut store, commitment_hash)?))
+}
+
+/// Rebuilds the commitment tree from `COMMITMENT_SNAPSHOT`, to recover an in-memory tree that
+/// went out of sync without restarting. Requires the `API_TOKEN` bearer token.
+async fn rebuild_tree_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    auth::require_bearer(&headers, state.api_token.as_deref())?;
+    let path = state.commitment_snapshot.as_ref().ok_or((
+        StatusCode::FORBIDDEN,
+        "endpoint disabled: COMMITMENT_SNAPSHOT is not configured".to_string(),
+    ))?;
+
+    // A snapshot not written yet means no commitment was ever added
+    let leaves = match CommitmentStore::load_leaves(path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        leaves => leaves.map_err(to_500)?,
+    };
+    let root = state
+        .commitments
+        .lock()
+        .map_err(to_500)?
+        .rebuild(&leaves)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    tracing::warn!("rebuilt commitment tree: {} leaves", leaves.len());
+
+    Ok(Json(RebuildTreeResponse {
+        root: format!("0x{}", hex::encode(root)),
+        leaf_count: leaves.len(),
     }))
 }
 
+/// Serves the inclusion proof of an existing commitment against the current root.
+async fn commitment_proof_handler(
+    State(state): State<AppState>,
+    Path(commitment_hash): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let commitment_hash = hex_to_array::<32>(&commitment_hash).map_err(to_500)?;
+
+    let mut store = state.commitments.lock().map_err(to_500)?;
+    Ok(Json(inclusion_proof_response(&mut store, commitment_hash)?))
+}
+
+/// Decodes the outputs a `proof_b64` commits to, for inspection. The proof is not verified, so
+/// the outputs are only as trustworthy as the proof's source; use the `/rpc` verify call for that.
+async fn decode_handler(
+    Json(req): Json<DecodeRequest>,
+) -> Result<Json<OutputsJson>, (StatusCode, String)> {
+    let proof = decode_proof_b64(&req.proof_b64)?;
+    Ok(Json(decode_outputs(&proof.public_values)?.into()))
+}
+
+/// Decodes public values as `GuestOutputs` without consuming them.
+fn decode_outputs(public_values: &SP1PublicValues) -> Result<GuestOutputs, (StatusCode, String)> {
+    let (outputs, _): (GuestOutputs, _) =
+        bincode::serde::decode_from_slice(public_values.as_slice(), bincode::config::legacy())
+            .map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("public values are not GuestOutputs: {e}"),
+                )
+            })?;
+    Ok(outputs)
+}
+
+async fn program_handler(State(state): State<AppState>) -> Json<ProgramInfoResponse> {
+    Json(ProgramInfoResponse {
+        program: state.programs.latest().info.clone(),
+        max_batch_size: state.max_batch_size,
+    })
+}
+
+async fn prove_schema_handler() -> Json<Schema> {
+    Json(schema_for!(ProveRequest))
+}
+
 /// ────────────────  Tokio main ────────────────
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let config = Config::from_env()?;
+    logging::init(config.log_format)?;
+    let state = AppState::new(&config)?;
+    if config.startup_self_test {
+        tokio::spawn(health::startup_self_test(state.clone()));
+    }
+
     let app = Router::new()
         .route("/prove", post(prove_handler))
-        .with_state(STATE.clone());
+        .route("/prove/reprice", post(reprice_handler))
+        .route("/rpc", post(rpc::rpc_handler))
+        .route("/cancel", post(cancel_handler))
+        .route("/commitments", post(add_commitment_handler))
+        .route("/commitments/:hash/proof", get(commitment_proof_handler))
+        .route("/admin/rebuild-tree", post(rebuild_tree_handler))
+        .route("/decode", post(decode_handler))
+        .route("/program", get(program_handler))
+        .route("/schema/prove", get(prove_schema_handler))
+        .route("/health", get(health::health_handler))
+        // gzip responses for clients sending `Accept-Encoding: gzip`; proof blobs shrink the most
+        .layer(CompressionLayer::new())
+        // Outermost, so the request span covers every other layer and each response is tagged
+        .layer(middleware::from_fn(request_id::propagate))
+        .with_state(state.clone());
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await?;
     println!("dark-pool server listening on {}", listener.local_addr()?);
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+    state.shutdown();
     Ok(())
 }
+
+/// Resolves on Ctrl-C or, on Unix, SIGTERM; in-flight requests then finish before `serve`
+/// returns.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("cannot listen for Ctrl-C: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("cannot listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    tracing::info!("shutting down");
+}
@@ -0,0 +1,56 @@
+//! ────────────────  Proving counters, summarized at shutdown  ────────────────
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+#[derive(Default)]
+pub struct Metrics {
+    proofs: AtomicU64,
+    failed_proofs: AtomicU64,
+    proving_millis: AtomicU64, // successful proofs only
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts one prove call; only successful ones add to the proving time.
+    pub fn record_proof(&self, elapsed: Duration, succeeded: bool) {
+        if succeeded {
+            self.proofs.fetch_add(1, Ordering::Relaxed);
+            self.proving_millis
+                .fetch_add(elapsed.as_millis() as u64, Ordering::Relaxed);
+        } else {
+            self.failed_proofs.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Logs the totals since startup, with the commitment store's `(hits, misses)`.
+    pub fn log_summary(&self, (cache_hits, cache_misses): (u64, u64)) {
+        let proofs = self.proofs.load(Ordering::Relaxed);
+        let average_proving_ms = self
+            .proving_millis
+            .load(Ordering::Relaxed)
+            .checked_div(proofs)
+            .unwrap_or(0);
+        let lookups = cache_hits + cache_misses;
+        let cache_hit_rate = if lookups == 0 {
+            0.0
+        } else {
+            cache_hits as f64 / lookups as f64
+        };
+
+        tracing::info!(
+            proofs,
+            failed_proofs = self.failed_proofs.load(Ordering::Relaxed),
+            average_proving_ms,
+            cache_hits,
+            cache_misses,
+            cache_hit_rate,
+            "shutdown summary"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! ────────────────  Spent nullifier hashes  ────────────────
+//!
+//! The hashes `/cancel` has published, which `/prove`, `/prove/reprice` and `execute` refuse.
+//! With `NULLIFIER_SNAPSHOT` set the set is saved after every insert and reloaded at startup, so
+//! a restart does not reopen cancelled orders to proving.
+//!
+//! The snapshot is the raw 32-byte hashes back to back in ascending order, with no header.
+
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Default)]
+pub struct NullifierSet {
+    spent: HashSet<[u8; 32]>,
+}
+
+impl NullifierSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.spent.len()
+    }
+
+    pub fn contains(&self, nullifier_hash: &[u8; 32]) -> bool {
+        self.spent.contains(nullifier_hash)
+    }
+
+    /// Marks `nullifier_hash` spent; returns false if it already was.
+    pub fn insert(&mut self, nullifier_hash: [u8; 32]) -> bool {
+        self.spent.insert(nullifier_hash)
+    }
+
+    /// Writes the snapshot to a temporary file next to `path`, then renames it over `path`, so a
+    /// crash mid-write leaves the previous snapshot intact.
+    pub fn save_to_file(&self, path: &Path) -> io::Result<()> {
+        let mut hashes: Vec<[u8; 32]> = self.spent.iter().copied().collect();
+        hashes.sort();
+
+        let tmp = tmp_path(path);
+        fs::write(&tmp, hashes.concat())?;
+        fs::rename(&tmp, path)
+    }
+
+    /// Reads a snapshot written by `save_to_file`. Fails with `InvalidData` if its length is not
+    /// a multiple of 32 bytes.
+    pub fn load_from_file(path: &Path) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        if bytes.len() % 32 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "nullifier snapshot is {} bytes, not a multiple of 32",
+                    bytes.len()
+                ),
+            ));
+        }
+
+        let spent = bytes.as_chunks::<32>().0.iter().copied().collect();
+        Ok(Self { spent })
+    }
+}
+
+/// Sibling of `path` that snapshots are written to before being renamed over it
+pub fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
@@ -0,0 +1,140 @@
+//! ────────────────  Guest programs served side by side  ────────────────
+//!
+//! The ELF compiled into the server is the latest program and the default. Older ELFs can be
+//! loaded from `PROGRAM_DIR` so clients pinning an old vkey keep working through an upgrade
+//! that keeps the input and output layout; requests select one by `vkey_hash`.
+//!
+//! The server only builds the compiled-in input layout and decodes its `GuestOutputs`, so only
+//! rebuilds at the same `PROTOCOL_VERSION` are supported. Each file is named for the version it
+//! was built at, as `<name>-v<version>[.elf]` (e.g. `order-v15.elf`), and a program at another
+//! version fails the load at startup rather than being fed a layout it cannot parse.
+
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use axum::http::StatusCode;
+use sha2::{Digest, Sha256};
+use sp1_prover::components::CpuProverComponents;
+use sp1_sdk::{HashableKey, Prover, SP1ProvingKey, SP1VerifyingKey};
+
+use crate::ProgramResponse;
+
+/// A guest ELF with its proving and verifying keys.
+pub struct Program {
+    pub elf: Vec<u8>,
+    pub pk: Arc<SP1ProvingKey>,
+    pub vk: Arc<SP1VerifyingKey>,
+    pub info: ProgramResponse,
+}
+
+impl Program {
+    fn setup(
+        client: &dyn Prover<CpuProverComponents>,
+        elf: Vec<u8>,
+        protocol_version: u32,
+    ) -> Self {
+        let (pk, vk) = client.setup(&elf);
+        let info = ProgramResponse {
+            vkey_hash: format!("0x{}", hex::encode(vk.hash_bytes())),
+            elf_sha256: format!("0x{}", hex::encode(Sha256::digest(&elf))),
+            protocol_version,
+        };
+        Program {
+            elf,
+            pk: Arc::new(pk),
+            vk: Arc::new(vk),
+            info,
+        }
+    }
+}
+
+pub struct ProgramRegistry {
+    programs: HashMap<String, Arc<Program>>, // vkey hash → program
+    latest: Arc<Program>,
+}
+
+impl ProgramRegistry {
+    /// Sets up `latest` and every file in `dir`, which must all be guest ELFs named for
+    /// `protocol_version`. Fails on a file named for any other version.
+    pub fn load(
+        client: &dyn Prover<CpuProverComponents>,
+        latest: &[u8],
+        protocol_version: u32,
+        dir: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        let latest = Arc::new(Program::setup(client, latest.to_vec(), protocol_version));
+        let mut programs = HashMap::from([(latest.info.vkey_hash.clone(), latest.clone())]);
+
+        if let Some(dir) = dir {
+            let entries = fs::read_dir(dir)
+                .map_err(|e| anyhow::anyhow!("cannot read PROGRAM_DIR {dir}: {e}"))?;
+            for entry in entries {
+                let path = entry?.path();
+                if !path.is_file() {
+                    continue;
+                }
+                let version = protocol_version_from_name(&path)?;
+                if version != protocol_version {
+                    anyhow::bail!(
+                        "PROGRAM_DIR file {} is protocol version {version}; this server only \
+                         builds inputs for version {protocol_version}",
+                        path.display()
+                    );
+                }
+                let program = Program::setup(client, fs::read(&path)?, version);
+                tracing::info!(
+                    "loaded program {} (protocol version {version}) from {}",
+                    program.info.vkey_hash,
+                    path.display()
+                );
+                programs
+                    .entry(program.info.vkey_hash.clone())
+                    .or_insert_with(|| Arc::new(program));
+            }
+        }
+
+        Ok(Self { programs, latest })
+    }
+
+    pub fn latest(&self) -> &Arc<Program> {
+        &self.latest
+    }
+
+    /// The program with `vkey_hash` (0x-prefixed or not), or the latest when none is given
+    pub fn get(&self, vkey_hash: Option<&str>) -> Result<Arc<Program>, (StatusCode, String)> {
+        let Some(vkey_hash) = vkey_hash else {
+            return Ok(self.latest.clone());
+        };
+        let key = format!(
+            "0x{}",
+            vkey_hash
+                .strip_prefix("0x")
+                .unwrap_or(vkey_hash)
+                .to_lowercase()
+        );
+        self.programs.get(&key).cloned().ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("unknown vkey_hash {vkey_hash}; GET /program reports the latest"),
+            )
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.programs.len()
+    }
+}
+
+/// Version in a `PROGRAM_DIR` file name of the form `<name>-v<version>[.elf]`
+fn protocol_version_from_name(path: &Path) -> anyhow::Result<u32> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.rsplit_once("-v"))
+        .and_then(|(_, version)| version.parse().ok())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "PROGRAM_DIR file {} must be named <name>-v<protocol version>, e.g. order-v{}.elf",
+                path.display(),
+                fibonacci_lib::PROTOCOL_VERSION
+            )
+        })
+}
@@ -0,0 +1,137 @@
+//! ────────────────  Tip-ordered admission to proving  ────────────────
+//!
+//! Proving capacity is limited to a fixed number of slots. Requests waiting for a slot are
+//! admitted highest tip first; equal tips are admitted in arrival order. Waiters await a
+//! `Notify` rather than blocking a runtime worker, and the prove itself runs on the blocking
+//! pool.
+
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use tokio::{sync::Notify, task::JoinError};
+
+type Ticket = (u64, Reverse<u64>); // (tip, arrival) — max-heap on tip, then FIFO
+
+pub struct ProveQueue {
+    state: Mutex<QueueState>,
+    admitted: Notify,
+    slots: usize,
+}
+
+struct QueueState {
+    waiting: BinaryHeap<Ticket>,
+    active: usize,
+    next_arrival: u64,
+}
+
+impl ProveQueue {
+    pub fn new(slots: usize) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                waiting: BinaryHeap::new(),
+                active: 0,
+                next_arrival: 0,
+            }),
+            admitted: Notify::new(),
+            slots: slots.max(1),
+        }
+    }
+
+    /// Waits until this request is the highest-priority waiter and a slot is free, then runs
+    /// `f` in that slot on the blocking pool. The slot is held until `f` returns, even if the
+    /// caller stops waiting for it. Fails only if `f` panics.
+    pub async fn run<R: Send + 'static>(
+        self: &Arc<Self>,
+        tip: u64,
+        f: impl FnOnce() -> R + Send + 'static,
+    ) -> Result<R, JoinError> {
+        let slot = self.admit(tip).await;
+        tokio::task::spawn_blocking(move || {
+            let _slot = slot;
+            f()
+        })
+        .await
+    }
+
+    /// Requests currently waiting for a slot.
+    pub fn waiting(&self) -> usize {
+        self.state().waiting.len()
+    }
+
+    async fn admit(self: &Arc<Self>, tip: u64) -> Slot {
+        let ticket = {
+            let mut state = self.state();
+            let ticket = (tip, Reverse(state.next_arrival));
+            state.next_arrival += 1;
+            state.waiting.push(ticket);
+            ticket
+        };
+        let mut waiter = Waiter {
+            queue: self,
+            ticket,
+            admitted: false,
+        };
+
+        loop {
+            // Registered before checking, so a wakeup between the check and the await is kept
+            let notified = self.admitted.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            {
+                let mut state = self.state();
+                if state.active < self.slots && state.waiting.peek() == Some(&ticket) {
+                    state.waiting.pop();
+                    state.active += 1;
+                    waiter.admitted = true;
+                    tracing::info!(
+                        "proving slot taken (tip {tip}, {} still waiting)",
+                        state.waiting.len()
+                    );
+                    break;
+                }
+            }
+            notified.await;
+        }
+        // The next waiter may fit in another free slot
+        self.admitted.notify_waiters();
+        Slot(self.clone())
+    }
+
+    fn state(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Withdraws a ticket whose request stopped waiting (e.g. the client disconnected), so it does
+/// not hold up the waiters behind it.
+struct Waiter<'a> {
+    queue: &'a ProveQueue,
+    ticket: Ticket,
+    admitted: bool,
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        if !self.admitted {
+            self.queue
+                .state()
+                .waiting
+                .retain(|ticket| *ticket != self.ticket);
+            self.queue.admitted.notify_waiters();
+        }
+    }
+}
+
+/// Frees its slot when dropped, including when the prove panics.
+struct Slot(Arc<ProveQueue>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.state().active -= 1;
+        self.0.admitted.notify_waiters();
+    }
+}
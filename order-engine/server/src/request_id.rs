@@ -0,0 +1,67 @@
+//! ────────────────  `X-Request-Id` on every request  ────────────────
+//!
+//! An incoming `X-Request-Id` is kept; otherwise the server generates one. Either way the id is
+//! recorded on a span wrapping the whole request, so every log line the request produces
+//! carries it, and it is echoed in the response's `X-Request-Id` header.
+
+use std::{
+    sync::{
+        OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied id kept; longer ones are replaced by a generated id
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+pub async fn propagate(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&X_REQUEST_ID)
+        .filter(|id| is_usable(id))
+        .cloned()
+        .unwrap_or_else(generate);
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = request_id.to_str().unwrap_or_default(),
+        method = %request.method(),
+        path = request.uri().path(),
+    );
+    let mut response = next.run(request).instrument(span).await;
+    response.headers_mut().insert(X_REQUEST_ID, request_id);
+    response
+}
+
+/// Printable ASCII, non-empty and at most `MAX_REQUEST_ID_LEN` bytes, so it logs as sent
+fn is_usable(id: &HeaderValue) -> bool {
+    id.to_str()
+        .is_ok_and(|id| !id.trim().is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+}
+
+/// Process start time and a counter, like `/prove` request ids, so ids stay unique across
+/// restarts
+fn generate() -> HeaderValue {
+    static PREFIX: OnceLock<u64> = OnceLock::new();
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+
+    let prefix = PREFIX.get_or_init(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0)
+    });
+    let id = format!("{prefix:016x}{:08x}", NEXT.fetch_add(1, Ordering::Relaxed));
+    HeaderValue::from_str(&id).expect("hex digits are a valid header value")
+}
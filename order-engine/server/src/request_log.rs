@@ -0,0 +1,88 @@
+//! ────────────────  On-disk log of submitted prove requests  ────────────────
+//!
+//! Each `/prove` request is written to `{dir}/{request_id}.json` before proving, so a failed
+//! prove can be investigated and replayed later through the script. Logs keep the public inputs
+//! and order terms; the wallet address is replaced by a keyed hash and every private witness is
+//! zeroed (see `ProveRequest::sanitized`), so a replay needs the witness from the client again.
+//! Only the newest `max_files` logs are kept.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+pub struct RequestLog {
+    dir: PathBuf,
+    max_files: usize,
+    key: Vec<u8>, // `REQUEST_LOG_KEY`, the HMAC key for wallet addresses
+}
+
+impl RequestLog {
+    /// Creates `dir` if needed.
+    pub fn new(dir: impl Into<PathBuf>, max_files: usize, key: Vec<u8>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            max_files,
+            key,
+        })
+    }
+
+    /// HMAC-SHA256 of `data` under the server's log key. Unlike a plain hash, it cannot be
+    /// reversed by hashing every known address without the key.
+    pub fn keyed_hash(&self, data: &[u8]) -> [u8; 32] {
+        const BLOCK: usize = 64;
+        let mut block = [0u8; BLOCK];
+        if self.key.len() > BLOCK {
+            block[..32].copy_from_slice(&Sha256::digest(&self.key));
+        } else {
+            block[..self.key.len()].copy_from_slice(&self.key);
+        }
+
+        let inner = Sha256::new()
+            .chain_update(block.map(|b| b ^ 0x36))
+            .chain_update(data)
+            .finalize();
+        Sha256::new()
+            .chain_update(block.map(|b| b ^ 0x5c))
+            .chain_update(inner)
+            .finalize()
+            .into()
+    }
+
+    /// Writes `request` as `{request_id}.json`, prunes the oldest logs and returns the path.
+    pub fn write<T: Serialize>(&self, request_id: &str, request: &T) -> io::Result<PathBuf> {
+        let path = self.dir.join(format!("{request_id}.json"));
+        fs::write(&path, serde_json::to_vec_pretty(request)?)?;
+        self.prune()?;
+        Ok(path)
+    }
+
+    /// Deletes the oldest `.json` logs beyond `max_files`.
+    fn prune(&self) -> io::Result<()> {
+        let mut logs: Vec<(std::time::SystemTime, PathBuf)> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| is_log(path))
+            .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+            .collect();
+
+        if logs.len() <= self.max_files {
+            return Ok(());
+        }
+
+        logs.sort();
+        for (_, path) in &logs[..logs.len() - self.max_files] {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_log(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
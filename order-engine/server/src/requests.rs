@@ -0,0 +1,67 @@
+//! ────────────────  Short-lived state for submitted requests  ────────────────
+//!
+//! Holds decoded guest inputs (including the private nullifier) so a client can re-attempt the
+//! same order, e.g. against fresh market conditions, without re-sending it. Entries expire
+//! after a fixed TTL and are never persisted.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+pub struct RequestStore<T> {
+    entries: HashMap<String, (Instant, T)>, // id → (expiry, value)
+    ttl: Duration,
+    id_prefix: u64,
+    next_id: u64,
+}
+
+impl<T: Clone> RequestStore<T> {
+    pub fn new(ttl: Duration) -> Self {
+        let id_prefix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+
+        Self {
+            entries: HashMap::new(),
+            ttl,
+            id_prefix,
+            next_id: 0,
+        }
+    }
+
+    /// Stores `value` and returns its new request id.
+    pub fn insert(&mut self, value: T) -> String {
+        self.prune();
+
+        self.next_id += 1;
+        let id = format!("{:016x}{:08x}", self.id_prefix, self.next_id);
+        self.entries
+            .insert(id.clone(), (Instant::now() + self.ttl, value));
+        id
+    }
+
+    /// Returns the value for `id` unless it is unknown or expired.
+    pub fn get(&mut self, id: &str) -> Option<T> {
+        self.prune();
+        self.entries.get(id).map(|(_, value)| value.clone())
+    }
+
+    /// Replaces the value for a live `id`, keeping its original expiry.
+    pub fn update(&mut self, id: &str, value: T) -> bool {
+        self.prune();
+        match self.entries.get_mut(id) {
+            Some(entry) => {
+                entry.1 = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn prune(&mut self) {
+        let now = Instant::now();
+        self.entries.retain(|_, (expiry, _)| *expiry > now);
+    }
+}
@@ -0,0 +1,279 @@
+//! ────────────────  Batched JSON-RPC style calls  ────────────────
+//!
+//! `POST /rpc` takes an array of `{ "method": "execute" | "prove" | "verify", "params": {...} }`
+//! and answers with an array in the same order. Each entry succeeds or fails on its own as
+//! `{ "result": ... }` or `{ "error": { "code", "message" } }`. A batch carrying more orders
+//! (execute and prove calls) than `MAX_BATCH_SIZE` is rejected whole with 400 before any call
+//! runs.
+
+use axum::{Json, extract::State, http::StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use fibonacci_lib::{GuestOutputs, is_fresh};
+
+use crate::{
+    AppState, ProveRequest, checked_inputs, decode_outputs, decode_proof_b64, prove_request,
+    run_execute, to_500, unix_now,
+};
+
+#[derive(Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "lowercase")]
+enum RpcCall {
+    /// Same params as `/prove`; runs the guest without proving
+    Execute(ProveRequest),
+    /// Same params and result as `/prove`
+    Prove(ProveRequest),
+    /// Verifies a `proof_b64` returned by `prove` against the vkey of the program it selects
+    Verify(VerifyParams),
+}
+
+#[derive(Deserialize)]
+struct VerifyParams {
+    proof_b64: String,
+    /// Program the proof is checked against; the latest when omitted
+    #[serde(default)]
+    vkey_hash: Option<String>,
+    /// Hex public values the caller attributes to the proof. Only ever compared with the values
+    /// the proof commits, which are the ones verified; a mismatch is an error.
+    #[serde(default)]
+    public_values: Option<String>,
+}
+
+#[derive(Serialize)]
+struct VerifyResult {
+    verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    /// Committed market timestamp older than `VERIFY_MAX_AGE_SECS`; absent when no maximum age
+    /// is configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stale: Option<bool>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RpcResponse {
+    Result(Value),
+    Error { code: u16, message: String },
+}
+
+impl From<Result<Value, (StatusCode, String)>> for RpcResponse {
+    fn from(result: Result<Value, (StatusCode, String)>) -> Self {
+        match result {
+            Ok(value) => RpcResponse::Result(value),
+            Err((status, message)) => RpcResponse::Error {
+                code: status.as_u16(),
+                message,
+            },
+        }
+    }
+}
+
+pub async fn rpc_handler(
+    State(state): State<AppState>,
+    Json(calls): Json<Vec<Value>>,
+) -> Result<Json<Vec<RpcResponse>>, (StatusCode, String)> {
+    if calls.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "empty batch".to_string()));
+    }
+    if calls.len() > state.rpc_max_batch {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!(
+                "batch of {} calls exceeds the limit of {}",
+                calls.len(),
+                state.rpc_max_batch
+            ),
+        ));
+    }
+    check_batch_size(&calls, state.max_batch_size)?;
+
+    let mut responses = Vec::with_capacity(calls.len());
+    for call in calls {
+        responses.push(dispatch(&state, call).await.into());
+    }
+    Ok(Json(responses))
+}
+
+/// Rejects `calls` carrying more orders than `max_batch_size`. Execute and prove calls each
+/// carry one order; verify calls carry none.
+fn check_batch_size(calls: &[Value], max_batch_size: usize) -> Result<(), (StatusCode, String)> {
+    let orders = calls
+        .iter()
+        .filter(|call| {
+            matches!(
+                call.get("method").and_then(Value::as_str),
+                Some("execute" | "prove")
+            )
+        })
+        .count();
+    if orders > max_batch_size {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("batch of {orders} orders exceeds MAX_BATCH_SIZE of {max_batch_size}"),
+        ));
+    }
+    Ok(())
+}
+
+async fn dispatch(state: &AppState, call: Value) -> Result<Value, (StatusCode, String)> {
+    let call: RpcCall = serde_json::from_value(call)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid call: {e}")))?;
+
+    // Execute and verify run the zkVM or a proof verifier, so they go to the blocking pool like
+    // proves do (`prove_request` waits for a proving slot first)
+    match call {
+        RpcCall::Execute(req) => {
+            blocking(state, move |state| {
+                let program = state.programs.get(req.vkey_hash.as_deref())?;
+                let inputs = checked_inputs(state, &req)?;
+                let mut response = run_execute(state, &program, &inputs)?;
+                response.outputs.add_decimals(req.decimals);
+                serde_json::to_value(response).map_err(to_500)
+            })
+            .await
+        }
+        RpcCall::Prove(req) => {
+            serde_json::to_value(prove_request(state, &req).await?).map_err(to_500)
+        }
+        RpcCall::Verify(params) => {
+            blocking(state, move |state| {
+                serde_json::to_value(verify(state, &params)?).map_err(to_500)
+            })
+            .await
+        }
+    }
+}
+
+/// Runs `f` on the blocking pool with its own handle on the state
+async fn blocking(
+    state: &AppState,
+    f: impl FnOnce(&AppState) -> Result<Value, (StatusCode, String)> + Send + 'static,
+) -> Result<Value, (StatusCode, String)> {
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || f(&state))
+        .await
+        .map_err(to_500)?
+}
+
+/// Whether the committed market timestamp is older than `max_age_secs` at `now`; `None` when no
+/// maximum age is configured (0). Every disclosure level commits the timestamp.
+fn proof_stale(outputs: &GuestOutputs, now: u64, max_age_secs: u64) -> Option<bool> {
+    (max_age_secs > 0).then(|| !is_fresh(outputs.block_timestamp, now, max_age_secs))
+}
+
+fn verify(state: &AppState, params: &VerifyParams) -> Result<VerifyResult, (StatusCode, String)> {
+    let program = state.programs.get(params.vkey_hash.as_deref())?;
+    let proof = decode_proof_b64(&params.proof_b64)?;
+
+    if let Some(claimed) = &params.public_values {
+        let claimed = hex::decode(claimed.strip_prefix("0x").unwrap_or(claimed)).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("invalid public_values: {e}"),
+            )
+        })?;
+        if claimed != proof.public_values.as_slice() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "public_values do not match the values the proof commits".to_string(),
+            ));
+        }
+    }
+
+    let outputs = decode_outputs(&proof.public_values)?;
+    let stale = proof_stale(&outputs, unix_now(), state.verify_max_age_secs);
+    if stale == Some(true) && state.verify_strict_age {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "proof market timestamp is older than {}s",
+                state.verify_max_age_secs
+            ),
+        ));
+    }
+
+    // A proof against any other tree shows nothing about the pool's commitments
+    let known_root = state
+        .commitments
+        .lock()
+        .map_err(to_500)?
+        .is_recent_root(&outputs.merkle_root);
+
+    Ok(match state.client.verify(&proof, &program.vk) {
+        Ok(()) if !known_root => VerifyResult {
+            verified: false,
+            reason: Some("merkle_root is not a recent commitment tree root".to_string()),
+            stale,
+        },
+        Ok(()) => VerifyResult {
+            verified: true,
+            reason: None,
+            stale,
+        },
+        Err(e) => VerifyResult {
+            verified: false,
+            reason: Some(e.to_string()),
+            stale,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fibonacci_lib::{AmountRepr, NullifierBits, OrderDomain};
+    use serde_json::json;
+
+    fn calls(method: &str, count: usize) -> Vec<Value> {
+        vec![json!({ "method": method, "params": {} }); count]
+    }
+
+    #[test]
+    fn batch_one_over_max_batch_size_is_rejected() {
+        assert!(check_batch_size(&calls("prove", 4), 4).is_ok());
+
+        let mut over = calls("execute", 4);
+        over.extend(calls("prove", 1));
+        let (status, _) = check_batch_size(&over, 4).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    /// Outputs of a minimal-disclosure proof at market timestamp 1000
+    fn minimal_outputs() -> GuestOutputs {
+        GuestOutputs {
+            valid: true,
+            nullifier_hash: [1u8; 32],
+            nullifier_bits: NullifierBits::Bits256,
+            merkle_root: [2u8; 32],
+            domain: OrderDomain {
+                chain_id: 17000,
+                pool_address: [0xCu8; 20],
+                amount_repr: AmountRepr::U64,
+            },
+            block_timestamp: 1_000,
+            metadata_present: false,
+            balance_root: None,
+            spent_root: None,
+            price_root: None,
+            cross_check_ok: None,
+            settlement: None,
+        }
+    }
+
+    #[test]
+    fn proof_with_an_old_committed_timestamp_is_stale() {
+        let outputs = minimal_outputs();
+        assert_eq!(proof_stale(&outputs, 1_300, 300), Some(false));
+        assert_eq!(proof_stale(&outputs, 1_301, 300), Some(true));
+        assert_eq!(proof_stale(&outputs, 1_000_000, 0), None);
+    }
+
+    #[test]
+    fn verify_calls_do_not_count_towards_max_batch_size() {
+        let mut batch = calls("prove", 4);
+        batch.extend(calls("verify", 4));
+        assert!(check_batch_size(&batch, 4).is_ok());
+    }
+}